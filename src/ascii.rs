@@ -1,17 +1,232 @@
-use bevy::prelude::*;
+use std::fs;
 
-use crate::TILE_SIZE;
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{cp437::Cp437Mapping, TILE_SIZE};
+
+const GLYPH_SHEETS_PATH: &str = "assets/glyph_sheets.ron";
 
 pub struct AsciiPlugin;
 
 #[derive(Component)]
 pub struct AsciiText;
+
+#[derive(Component)]
+pub struct AsciiSprite;
+
+#[derive(Component)]
+pub struct AsciiChar(pub char);
+
 #[derive(Resource)]
-pub struct AsciiSheet(pub Handle<TextureAtlas>);
+pub struct AsciiSheet {
+    pub handle: Handle<TextureAtlas>,
+    pub mapping: Cp437Mapping,
+    pub glyphs: usize,
+    // Size of one glyph in the source image, in pixels
+    pub cell_size: Vec2,
+}
+
+#[derive(Resource)]
+pub struct GlyphSheets {
+    pub sheets: Vec<GlyphSheet>,
+    pub active: usize,
+}
+
+pub struct GlyphSheet {
+    pub name: String,
+    pub handle: Handle<TextureAtlas>,
+    pub mapping: Cp437Mapping,
+    pub glyphs: usize,
+    pub cell_size: Vec2,
+}
+
+pub struct SwitchGlyphSheet(pub String);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    Top,
+    #[default]
+    Middle,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextLayout {
+    pub horizontal: HorizontalAlign,
+    pub vertical: VerticalAlign,
+    pub line_spacing: f32,
+}
+
+#[derive(Deserialize)]
+pub struct GlyphSheetsConfig {
+    pub default: String,
+    pub sheets: Vec<GlyphSheetConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GlyphSheetConfig {
+    pub name: String,
+    pub image: String,
+    pub cell_size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+    #[serde(default)]
+    pub padding: Option<Vec2>,
+    #[serde(default)]
+    pub offset: Option<Vec2>,
+    #[serde(default)]
+    pub mapping: MappingConfig,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct MappingConfig {
+    #[serde(default)]
+    pub overrides: HashMap<char, usize>,
+    #[serde(default)]
+    pub fallback: Option<usize>,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            horizontal: HorizontalAlign::Left,
+            vertical: VerticalAlign::Middle,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+impl TextLayout {
+    pub fn new(horizontal: HorizontalAlign, vertical: VerticalAlign) -> Self {
+        Self {
+            horizontal,
+            vertical,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+}
+
+impl Default for GlyphSheetsConfig {
+    fn default() -> Self {
+        Self {
+            default: "ascii".to_string(),
+            sheets: vec![GlyphSheetConfig {
+                name: "ascii".to_string(),
+                image: "Ascii.png".to_string(),
+                cell_size: Vec2::splat(9.0),
+                columns: 16,
+                rows: 16,
+                padding: Some(Vec2::splat(2.0)),
+                offset: None,
+                mapping: MappingConfig::default(),
+            }],
+        }
+    }
+}
+
+impl GlyphSheetsConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let config: GlyphSheetsConfig = ron::from_str(&contents).map_err(|err| err.to_string())?;
+        if config.sheets.is_empty() {
+            return Err("No glyph sheets defined".to_string());
+        }
+        for sheet in config.sheets.iter() {
+            sheet.validate()?;
+        }
+        Ok(config)
+    }
+}
+
+impl GlyphSheetConfig {
+    // Out of range glyphs are replaced by the fallback, so it and the overrides have to be
+    // on the sheet
+    fn validate(&self) -> Result<(), String> {
+        let glyphs = self.columns * self.rows;
+        let fallback = Cp437Mapping::from(&self.mapping).fallback;
+        if fallback >= glyphs {
+            return Err(format!(
+                "Fallback glyph {} is outside sheet {} ({} glyphs)",
+                fallback, self.name, glyphs
+            ));
+        }
+        for (char, index) in self.mapping.overrides.iter() {
+            if *index >= glyphs {
+                return Err(format!(
+                    "Glyph {} for {:?} is outside sheet {} ({} glyphs)",
+                    index, char, self.name, glyphs
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&MappingConfig> for Cp437Mapping {
+    fn from(config: &MappingConfig) -> Self {
+        let mut mapping = Cp437Mapping::default();
+        if let Some(fallback) = config.fallback {
+            mapping = mapping.with_fallback(fallback);
+        }
+        for (char, index) in config.overrides.iter() {
+            mapping = mapping.with_override(*char, *index);
+        }
+        mapping
+    }
+}
+
+impl GlyphSheets {
+    pub fn current(&self) -> &GlyphSheet {
+        &self.sheets[self.active]
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.sheets.iter().position(|sheet| sheet.name == name)
+    }
+}
+
+impl AsciiSheet {
+    // The fallback stands in for indices past the end of the sheet, which is checked on load
+    pub fn glyph_index(&self, index: usize) -> usize {
+        if index < self.glyphs {
+            index
+        } else {
+            warn!("Index {} out of Ascii Range", index);
+            self.mapping.fallback
+        }
+    }
+}
+
+impl GlyphSheet {
+    fn to_ascii_sheet(&self) -> AsciiSheet {
+        AsciiSheet {
+            handle: self.handle.clone(),
+            mapping: self.mapping.clone(),
+            glyphs: self.glyphs,
+            cell_size: self.cell_size,
+        }
+    }
+}
 
 impl Plugin for AsciiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system_to_stage(StartupStage::PreStartup, load_ascii);
+        app.add_event::<SwitchGlyphSheet>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, load_ascii)
+            .add_system(switch_glyph_sheet);
     }
 }
 
@@ -20,20 +235,35 @@ pub fn spawn_ascii_text(
     ascii: &AsciiSheet,
     to_print: &str,
     left_center: Vec3,
+) -> Entity {
+    spawn_ascii_text_aligned(
+        commands,
+        ascii,
+        to_print,
+        left_center - Vec3::new(TILE_SIZE / 2.0, 0.0, 0.0),
+        TextLayout::default(),
+    )
+}
+
+pub fn spawn_ascii_text_aligned(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    to_print: &str,
+    anchor: Vec3,
+    layout: TextLayout,
 ) -> Entity {
     let color = Color::rgb(0.8, 0.8, 0.8);
     let mut character_sprites = Vec::new();
 
-    for (i, char) in to_print.chars().enumerate() {
-        assert!(char as usize <= 255);
-        character_sprites.push(spawn_ascii_sprite(
+    for (char, offset) in layout_text(to_print, layout) {
+        character_sprites.push(spawn_ascii_char(
             commands,
             ascii,
-            char as usize,
+            char,
             color,
             Vec3 {
-                x: i as f32 * TILE_SIZE,
-                y: 0.0,
+                x: offset.x * TILE_SIZE,
+                y: offset.y * TILE_SIZE,
                 z: 0.0,
             },
             Vec3::splat(1.0),
@@ -44,7 +274,7 @@ pub fn spawn_ascii_text(
         .spawn(SpatialBundle::default())
         .insert(Name::new(format!("Text - {}", to_print)))
         .insert(Transform {
-            translation: left_center,
+            translation: anchor,
             ..Default::default()
         })
         .insert(GlobalTransform::default())
@@ -53,6 +283,65 @@ pub fn spawn_ascii_text(
         .id()
 }
 
+// Size of the text block in tiles
+pub fn measure_text(text: &str, layout: TextLayout) -> Vec2 {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    Vec2::new(
+        width as f32,
+        1.0 + (lines.len() - 1) as f32 * layout.line_spacing,
+    )
+}
+
+// Glyph centers in tiles, relative to the layout anchor
+pub fn layout_text(text: &str, layout: TextLayout) -> Vec<(char, Vec2)> {
+    let size = measure_text(text, layout);
+    let top = match layout.vertical {
+        VerticalAlign::Top => 0.0,
+        VerticalAlign::Middle => size.y / 2.0,
+        VerticalAlign::Bottom => size.y,
+    };
+
+    let mut glyphs = Vec::new();
+    for (row, line) in text.split('\n').enumerate() {
+        let length = line.chars().count() as f32;
+        let left = match layout.horizontal {
+            HorizontalAlign::Left => 0.0,
+            HorizontalAlign::Center => -length / 2.0,
+            HorizontalAlign::Right => -length,
+        };
+        let y = top - row as f32 * layout.line_spacing - 0.5;
+        for (column, char) in line.chars().enumerate() {
+            glyphs.push((char, Vec2::new(left + column as f32 + 0.5, y)));
+        }
+    }
+    glyphs
+}
+
+pub fn spawn_ascii_char(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    char: char,
+    color: Color,
+    translation: Vec3,
+    scale: Vec3,
+) -> Entity {
+    let sprite = spawn_ascii_sprite(
+        commands,
+        ascii,
+        ascii.mapping.index_of(char),
+        color,
+        translation,
+        scale,
+    );
+    commands.entity(sprite).insert(AsciiChar(char));
+    sprite
+}
+
 pub fn spawn_ascii_sprite(
     commands: &mut Commands,
     ascii: &AsciiSheet,
@@ -61,40 +350,173 @@ pub fn spawn_ascii_sprite(
     translation: Vec3,
     scale: Vec3,
 ) -> Entity {
-    assert!(index < 256, "Index out of Ascii Range");
-
-    let mut sprite = TextureAtlasSprite::new(index);
+    let mut sprite = TextureAtlasSprite::new(ascii.glyph_index(index));
     sprite.color = color;
     sprite.custom_size = Some(Vec2::splat(TILE_SIZE));
 
     commands
         .spawn(SpriteSheetBundle {
-            sprite: sprite,
-            texture_atlas: ascii.0.clone(),
+            sprite,
+            texture_atlas: ascii.handle.clone(),
             transform: Transform {
-                translation: translation,
-                scale: scale,
+                translation,
+                scale,
                 ..Default::default()
             },
             ..Default::default()
         })
+        .insert(AsciiSprite)
         .id()
 }
 
+#[allow(clippy::type_complexity)]
+fn switch_glyph_sheet(
+    mut switch_events: EventReader<SwitchGlyphSheet>,
+    mut sheets: ResMut<GlyphSheets>,
+    mut ascii: ResMut<AsciiSheet>,
+    mut sprite_query: Query<
+        (
+            &mut Handle<TextureAtlas>,
+            &mut TextureAtlasSprite,
+            Option<&AsciiChar>,
+        ),
+        With<AsciiSprite>,
+    >,
+) {
+    for event in switch_events.iter() {
+        let index = match sheets.find(&event.0) {
+            Some(index) => index,
+            None => {
+                warn!("Unknown glyph sheet {}", event.0);
+                continue;
+            }
+        };
+        sheets.active = index;
+        *ascii = sheets.current().to_ascii_sheet();
+
+        for (mut handle, mut sprite, char) in sprite_query.iter_mut() {
+            *handle = ascii.handle.clone();
+            if let Some(char) = char {
+                sprite.index = ascii.mapping.index_of(char.0);
+            }
+            sprite.index = ascii.glyph_index(sprite.index);
+        }
+    }
+}
+
 fn load_ascii(
     mut commands: Commands,
     assets: Res<AssetServer>,
     mut texture_aliases: ResMut<Assets<TextureAtlas>>,
 ) {
-    let image = assets.load("Ascii.png");
-    let atlas = TextureAtlas::from_grid(
-        image,
-        Vec2::splat(9.0),
-        16,
-        16,
-        Some(Vec2::splat(2.0)),
-        None,
-    );
-    let atlas_handle = texture_aliases.add(atlas);
-    commands.insert_resource(AsciiSheet(atlas_handle));
+    let config = GlyphSheetsConfig::load(GLYPH_SHEETS_PATH).unwrap_or_else(|err| {
+        warn!("Failed to load {}: {}", GLYPH_SHEETS_PATH, err);
+        GlyphSheetsConfig::default()
+    });
+
+    let sheets: Vec<GlyphSheet> = config
+        .sheets
+        .iter()
+        .map(|sheet| {
+            let image = assets.load(sheet.image.as_str());
+            let atlas = TextureAtlas::from_grid(
+                image,
+                sheet.cell_size,
+                sheet.columns,
+                sheet.rows,
+                sheet.padding,
+                sheet.offset,
+            );
+            GlyphSheet {
+                name: sheet.name.clone(),
+                handle: texture_aliases.add(atlas),
+                mapping: Cp437Mapping::from(&sheet.mapping),
+                glyphs: sheet.columns * sheet.rows,
+                cell_size: sheet.cell_size,
+            }
+        })
+        .collect();
+
+    let active = sheets
+        .iter()
+        .position(|sheet| sheet.name == config.default)
+        .unwrap_or(0);
+    let sheets = GlyphSheets { sheets, active };
+
+    commands.insert_resource(sheets.current().to_ascii_sheet());
+    commands.insert_resource(sheets);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_sheet(mapping: MappingConfig) -> GlyphSheetConfig {
+        GlyphSheetConfig {
+            name: "small".to_string(),
+            image: "Small.png".to_string(),
+            cell_size: Vec2::splat(8.0),
+            columns: 16,
+            rows: 2,
+            padding: None,
+            offset: None,
+            mapping,
+        }
+    }
+
+    #[test]
+    fn fallback_must_be_on_the_sheet() {
+        // The default fallback, '?', is glyph 63
+        assert!(small_sheet(MappingConfig::default()).validate().is_err());
+        let mapping = MappingConfig {
+            fallback: Some(31),
+            ..default()
+        };
+        assert!(small_sheet(mapping).validate().is_ok());
+    }
+
+    #[test]
+    fn overrides_must_be_on_the_sheet() {
+        let mapping = MappingConfig {
+            overrides: HashMap::from_iter([('x', 32)]),
+            fallback: Some(0),
+        };
+        assert!(small_sheet(mapping).validate().is_err());
+        assert!(GlyphSheetsConfig::default().sheets[0].validate().is_ok());
+    }
+
+    #[test]
+    fn measure_single_line() {
+        let size = measure_text("Health: 3", TextLayout::default());
+        assert_eq!(size, Vec2::new(9.0, 1.0));
+    }
+
+    #[test]
+    fn measure_multi_line() {
+        let layout = TextLayout::default().with_line_spacing(1.5);
+        assert_eq!(measure_text("ab\nabcd\nc", layout), Vec2::new(4.0, 4.0));
+        assert_eq!(measure_text("", layout), Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn left_middle_starts_at_anchor() {
+        let glyphs = layout_text("ab", TextLayout::default());
+        assert_eq!(
+            glyphs,
+            vec![('a', Vec2::new(0.5, 0.0)), ('b', Vec2::new(1.5, 0.0))]
+        );
+    }
+
+    #[test]
+    fn center_and_right_alignment() {
+        let layout = TextLayout::new(HorizontalAlign::Center, VerticalAlign::Top);
+        let glyphs = layout_text("abcd\nab", layout);
+        assert_eq!(glyphs[0], ('a', Vec2::new(-1.5, -0.5)));
+        assert_eq!(glyphs[4], ('a', Vec2::new(-0.5, -1.5)));
+
+        let layout = TextLayout::new(HorizontalAlign::Right, VerticalAlign::Bottom);
+        let glyphs = layout_text("ab\nc", layout);
+        assert_eq!(glyphs[1], ('b', Vec2::new(-0.5, 1.5)));
+        assert_eq!(glyphs[2], ('c', Vec2::new(-0.5, 0.5)));
+    }
 }
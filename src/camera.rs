@@ -0,0 +1,187 @@
+use bevy::{
+    prelude::*,
+    render::camera::{ScalingMode, Viewport},
+    window::WindowResized,
+};
+
+use crate::{
+    ascii::AsciiSheet,
+    settings::{ScalingStrategy, Settings},
+    RESOLUTION, TILE_SIZE,
+};
+
+pub struct CameraPlugin;
+
+// Half the size of the world area the camera shows, which HUD anchors and transitions cover
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct VisibleArea {
+    pub half_size: Vec2,
+}
+
+impl Default for VisibleArea {
+    fn default() -> Self {
+        Self {
+            half_size: Vec2::new(RESOLUTION, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CameraFit {
+    viewport: Option<Viewport>,
+    scaling_mode: ScalingMode,
+    scale: f32,
+    half_size: Vec2,
+}
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_camera)
+            .add_system(fit_camera_to_window);
+    }
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+fn fit_camera_to_window(
+    settings: Res<Settings>,
+    ascii: Res<AsciiSheet>,
+    windows: Res<Windows>,
+    mut resized_event: EventReader<WindowResized>,
+    mut area: ResMut<VisibleArea>,
+    mut camera_query: Query<(&mut Camera, &mut OrthographicProjection)>,
+) {
+    let resized = resized_event.iter().count() > 0;
+    if !resized && !settings.is_changed() && !ascii.is_changed() {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let physical = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    // Minimised
+    if physical.x < 1.0 || physical.y < 1.0 {
+        return;
+    }
+
+    let fit = fit_camera(
+        settings.scaling,
+        physical,
+        window.scale_factor() as f32,
+        ascii.cell_size.y,
+    );
+    for (mut camera, mut projection) in camera_query.iter_mut() {
+        camera.viewport = fit.viewport.clone();
+        projection.scaling_mode = fit.scaling_mode.clone();
+        projection.scale = fit.scale;
+    }
+    if area.half_size != fit.half_size {
+        area.half_size = fit.half_size;
+    }
+}
+
+fn fit_camera(
+    strategy: ScalingStrategy,
+    window: Vec2,
+    scale_factor: f32,
+    glyph_pixels: f32,
+) -> CameraFit {
+    match strategy {
+        ScalingStrategy::Letterbox => {
+            let size = if window.x / window.y > RESOLUTION {
+                Vec2::new((window.y * RESOLUTION).round(), window.y)
+            } else {
+                Vec2::new(window.x, (window.x / RESOLUTION).round())
+            };
+            let position = ((window - size) / 2.0).floor();
+            CameraFit {
+                viewport: Some(Viewport {
+                    physical_position: position.as_uvec2(),
+                    physical_size: size.as_uvec2(),
+                    ..default()
+                }),
+                scaling_mode: ScalingMode::FixedVertical(2.0),
+                scale: 1.0,
+                half_size: Vec2::new(RESOLUTION, 1.0),
+            }
+        }
+        ScalingStrategy::Expand => {
+            let half_size = if window.x / window.y > RESOLUTION {
+                Vec2::new(window.x / window.y, 1.0)
+            } else {
+                Vec2::new(RESOLUTION, RESOLUTION * window.y / window.x)
+            };
+            CameraFit {
+                viewport: None,
+                scaling_mode: ScalingMode::Auto {
+                    min_width: 2.0 * RESOLUTION,
+                    min_height: 2.0,
+                },
+                scale: 1.0,
+                half_size,
+            }
+        }
+        ScalingStrategy::PixelPerfect => {
+            // The largest whole multiple of the glyph size that still fits the play area,
+            // or 1 if the window is too small for even that
+            let unit_pixels = glyph_pixels / TILE_SIZE;
+            let play_area = Vec2::new(2.0 * RESOLUTION, 2.0) * unit_pixels;
+            let multiple = (window / play_area).min_element().floor().max(1.0);
+            let pixels_per_unit = unit_pixels * multiple;
+            CameraFit {
+                viewport: None,
+                // WindowSize works in logical pixels, so the scale undoes the scale factor
+                scaling_mode: ScalingMode::WindowSize,
+                scale: scale_factor / pixels_per_unit,
+                half_size: window / 2.0 / pixels_per_unit,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_centres_the_play_area() {
+        let fit = fit_camera(
+            ScalingStrategy::Letterbox,
+            Vec2::new(1000.0, 900.0),
+            1.0,
+            9.0,
+        );
+        let viewport = fit.viewport.unwrap();
+        assert_eq!(viewport.physical_size, UVec2::new(1000, 563));
+        assert_eq!(viewport.physical_position, UVec2::new(0, 168));
+        assert_eq!(fit.half_size, Vec2::new(RESOLUTION, 1.0));
+    }
+
+    #[test]
+    fn expand_shows_more_on_the_long_side() {
+        let fit = fit_camera(ScalingStrategy::Expand, Vec2::new(900.0, 900.0), 1.0, 9.0);
+        assert!(fit.viewport.is_none());
+        assert_eq!(fit.half_size.x, RESOLUTION);
+        assert!((fit.half_size.y - RESOLUTION).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pixel_perfect_uses_whole_multiples() {
+        // The play area is 320x180 glyph pixels, which fits five times into 1700x950
+        let fit = fit_camera(
+            ScalingStrategy::PixelPerfect,
+            Vec2::new(1700.0, 950.0),
+            2.0,
+            9.0,
+        );
+        let pixels_per_unit = 2.0 / fit.scale;
+        assert!((pixels_per_unit - 450.0).abs() < 1e-3);
+        assert!(fit.half_size.x >= RESOLUTION && fit.half_size.y >= 1.0);
+    }
+}
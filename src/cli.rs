@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use crate::{
+    combat::{find_enemy, ENEMIES},
+    player::MovementMode,
+    GameConfig, GameState,
+};
+
+pub const USAGE: &str = "\
+Usage: tutorial [options]
+
+Options:
+  --map <path>          Map file to load (default assets/map.txt)
+  --seed <number>       Seed for all randomness
+  --state <state>       Start in menu, overworld or combat
+  --enemy <name>        Enemy to fight when starting in combat
+  --movement <mode>     continuous or grid
+  --width <pixels>      Window width
+  --height <pixels>     Window height
+  --headless            Run without a window
+  --frames <count>      Quit after this many fixed steps (60 per second)
+  --record <path>       Record input to a replay file
+  --replay <path>       Play back a replay file
+  --help                Show this message";
+
+#[derive(Debug, Default)]
+pub struct CliOptions {
+    pub config: GameConfig,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub headless: bool,
+    pub help: bool,
+}
+
+impl CliOptions {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = CliOptions::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--map" => options.config.map_path = parse_map(value()?)?,
+                "--seed" => options.config.seed = Some(parse_number(&value()?)?),
+                "--state" => options.config.start_state = parse_state(&value()?)?,
+                "--enemy" => {
+                    let name = value()?;
+                    let enemy = find_enemy(&name).ok_or_else(|| {
+                        let known: Vec<&str> = ENEMIES.iter().map(|enemy| enemy.name).collect();
+                        format!("Unknown enemy {} (known: {})", name, known.join(", "))
+                    })?;
+                    options.config.enemy = Some(enemy.name.to_string());
+                }
+                "--movement" => options.config.movement_mode = parse_movement(&value()?)?,
+                "--width" => options.width = Some(parse_number(&value()?)?),
+                "--height" => options.height = Some(parse_number(&value()?)?),
+                "--headless" => options.headless = true,
+                "--frames" => options.config.max_frames = Some(parse_number(&value()?)?),
+                "--record" => options.config.record_path = Some(value()?),
+                "--replay" => options.config.replay_path = Some(value()?),
+                "--help" | "-h" => options.help = true,
+                _ => return Err(format!("Unknown option {}", arg)),
+            }
+        }
+
+        // Naming an enemy only makes sense if the game starts in its battle
+        if options.config.enemy.is_some() && options.config.start_state != GameState::Combat {
+            options.config.start_state = GameState::Combat;
+        }
+        Ok(options)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} is not a valid number", value))
+}
+
+fn parse_map(value: String) -> Result<String, String> {
+    if Path::new(&value).is_file() {
+        Ok(value)
+    } else {
+        Err(format!("Map file {} not found", value))
+    }
+}
+
+fn parse_state(value: &str) -> Result<GameState, String> {
+    match value.to_lowercase().as_str() {
+        "menu" => Ok(GameState::MainMenu),
+        "overworld" => Ok(GameState::Overworld),
+        "combat" => Ok(GameState::Combat),
+        _ => Err(format!(
+            "Unknown state {} (use menu, overworld or combat)",
+            value
+        )),
+    }
+}
+
+fn parse_movement(value: &str) -> Result<MovementMode, String> {
+    match value.to_lowercase().as_str() {
+        "continuous" => Ok(MovementMode::Continuous),
+        "grid" => Ok(MovementMode::Grid),
+        _ => Err(format!(
+            "Unknown movement mode {} (use continuous or grid)",
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        CliOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn defaults_without_arguments() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.config.start_state, GameState::MainMenu);
+        assert_eq!(options.config.seed, None);
+        assert!(!options.headless);
+    }
+
+    #[test]
+    fn reads_values() {
+        let options = parse(&[
+            "--map",
+            "assets/map.txt",
+            "--seed",
+            "42",
+            "--width",
+            "640",
+            "--headless",
+        ])
+        .unwrap();
+        assert_eq!(options.config.map_path, "assets/map.txt");
+        assert_eq!(options.config.seed, Some(42));
+        assert_eq!(options.width, Some(640.0));
+        assert!(options.headless);
+    }
+
+    #[test]
+    fn enemy_implies_combat() {
+        let options = parse(&["--enemy", "slime"]).unwrap();
+        assert_eq!(options.config.start_state, GameState::Combat);
+        assert_eq!(options.config.enemy.as_deref(), Some("Slime"));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--seed", "abc"]).is_err());
+        assert!(parse(&["--enemy", "dragon"]).is_err());
+        assert!(parse(&["--map", "assets/no_such_map.txt"]).is_err());
+        assert!(parse(&["--state", "keybindings"]).is_err());
+        assert!(parse(&["--fullscreen"]).is_err());
+    }
+}
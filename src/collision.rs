@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+
+// How far two boxes may overlap and still count as touching, to absorb float error
+const CONTACT_EPSILON: f32 = 1e-4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub center: Vec2,
+    pub half_size: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepHit {
+    pub time: f32,
+    pub normal: Vec2,
+}
+
+impl Aabb {
+    pub fn new(center: Vec2, size: Vec2) -> Self {
+        Self {
+            center,
+            half_size: size / 2.0,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        let distance = (self.center - other.center).abs();
+        let reach = self.half_size + other.half_size - Vec2::splat(CONTACT_EPSILON);
+        distance.x < reach.x && distance.y < reach.y
+    }
+
+    // Box covering everything this box touches while moving by delta
+    pub fn swept(&self, delta: Vec2) -> Aabb {
+        Aabb {
+            center: self.center + delta / 2.0,
+            half_size: self.half_size + delta.abs() / 2.0,
+        }
+    }
+}
+
+// Time of impact of a moving box against a static one, as a fraction of delta
+pub fn sweep(moving: &Aabb, delta: Vec2, obstacle: &Aabb) -> Option<SweepHit> {
+    let reach = moving.half_size + obstacle.half_size;
+    let offset = moving.center - obstacle.center;
+
+    let mut entry = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+
+    for axis in 0..2 {
+        if delta[axis] == 0.0 {
+            // Sliding along a face, or not lined up on this axis at all
+            if offset[axis].abs() >= reach[axis] - CONTACT_EPSILON {
+                return None;
+            }
+            continue;
+        }
+        let near = (-reach[axis].copysign(delta[axis]) - offset[axis]) / delta[axis];
+        let far = (reach[axis].copysign(delta[axis]) - offset[axis]) / delta[axis];
+        if near > entry {
+            entry = near;
+            normal = Vec2::ZERO;
+            normal[axis] = -delta[axis].signum();
+        }
+        exit = exit.min(far);
+    }
+
+    if entry > exit || exit <= 0.0 || entry > 1.0 {
+        return None;
+    }
+    if entry < 0.0 {
+        // Already overlapping, e.g. spawned inside a wall: backing out is free, anything
+        // heading further in is stopped where it stands
+        let penetration = -entry * (delta * normal).abs().max_element();
+        if penetration > CONTACT_EPSILON && delta.dot(offset) >= 0.0 {
+            return None;
+        }
+    }
+
+    Some(SweepHit {
+        time: entry.max(0.0),
+        normal,
+    })
+}
+
+// Moves the box by delta one axis at a time, stopping at walls and sliding along them.
+// Sweeping the axes separately means faces that are only touched never block, so the
+// seams between neighbouring tiles can't snag.
+pub fn move_and_slide(moving: &Aabb, delta: Vec2, obstacles: &[Aabb]) -> Vec2 {
+    let mut center = moving.center;
+
+    for step in [Vec2::new(delta.x, 0.0), Vec2::new(0.0, delta.y)] {
+        if step == Vec2::ZERO {
+            continue;
+        }
+        let current = Aabb {
+            center,
+            half_size: moving.half_size,
+        };
+        let time = obstacles
+            .iter()
+            .filter_map(|obstacle| sweep(&current, step, obstacle))
+            .map(|hit| hit.time)
+            .fold(1.0, f32::min);
+        center += step * time;
+    }
+    center
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(x: f32, y: f32) -> Aabb {
+        Aabb::new(Vec2::new(x, y), Vec2::ONE)
+    }
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn clamps_to_contact() {
+        let player = Aabb::new(Vec2::ZERO, Vec2::splat(0.8));
+        let result = move_and_slide(&player, Vec2::new(2.0, 0.0), &[tile(2.0, 0.0)]);
+        assert_close(result, Vec2::new(1.1, 0.0));
+    }
+
+    #[test]
+    fn does_not_tunnel_at_high_speed() {
+        let player = Aabb::new(Vec2::ZERO, Vec2::splat(0.8));
+        let result = move_and_slide(&player, Vec2::new(50.0, 0.0), &[tile(10.0, 0.0)]);
+        assert_close(result, Vec2::new(9.1, 0.0));
+    }
+
+    #[test]
+    fn slides_along_wall() {
+        let player = Aabb::new(Vec2::ZERO, Vec2::splat(0.8));
+        let wall = [tile(1.0, -1.0), tile(1.0, 0.0), tile(1.0, 1.0)];
+        let result = move_and_slide(&player, Vec2::new(1.0, 0.5), &wall);
+        assert_close(result, Vec2::new(0.1, 0.5));
+    }
+
+    #[test]
+    fn no_snag_on_seams_between_tiles() {
+        // Resting on a floor of separate tiles and walking across their seams
+        let player = Aabb::new(Vec2::new(0.0, 0.9), Vec2::splat(0.8));
+        let floor = [
+            tile(-1.0, 0.0),
+            tile(0.0, 0.0),
+            tile(1.0, 0.0),
+            tile(2.0, 0.0),
+        ];
+        let result = move_and_slide(&player, Vec2::new(1.5, -0.2), &floor);
+        assert_close(result, Vec2::new(1.5, 0.9));
+    }
+
+    #[test]
+    fn stops_in_inner_corner() {
+        let player = Aabb::new(Vec2::ZERO, Vec2::splat(0.8));
+        let corner = [tile(1.0, 0.0), tile(1.0, 1.0), tile(0.0, 1.0)];
+        let result = move_and_slide(&player, Vec2::new(1.0, 1.0), &corner);
+        assert_close(result, Vec2::new(0.1, 0.1));
+    }
+
+    #[test]
+    fn passes_outer_corner() {
+        let player = Aabb::new(Vec2::ZERO, Vec2::splat(0.8));
+        let result = move_and_slide(&player, Vec2::new(0.0, 3.0), &[tile(1.0, 1.0)]);
+        assert_close(result, Vec2::new(0.0, 3.0));
+    }
+
+    #[test]
+    fn can_leave_an_overlap() {
+        let player = Aabb::new(Vec2::new(0.5, 0.0), Vec2::splat(0.8));
+        let result = move_and_slide(&player, Vec2::new(1.0, 0.0), &[tile(0.0, 0.0)]);
+        assert_close(result, Vec2::new(1.5, 0.0));
+    }
+
+    #[test]
+    fn overlap_blocks_moving_deeper() {
+        let player = Aabb::new(Vec2::new(0.5, 0.0), Vec2::splat(0.8));
+        let hit = sweep(&player, Vec2::new(-1.0, 0.0), &tile(0.0, 0.0)).unwrap();
+        assert_eq!(hit.time, 0.0);
+        let result = move_and_slide(&player, Vec2::new(-1.0, 0.0), &[tile(0.0, 0.0)]);
+        assert_close(result, Vec2::new(0.5, 0.0));
+    }
+}
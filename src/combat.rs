@@ -1,20 +1,149 @@
-use std::iter;
+use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
 
 use crate::{
-    ascii::{spawn_ascii_sprite, spawn_ascii_text, AsciiSheet, AsciiText},
-    fadeout::create_fadeout,
+    ascii::{
+        spawn_ascii_char, spawn_ascii_text_aligned, AsciiSheet, AsciiText, HorizontalAlign,
+        TextLayout, VerticalAlign,
+    },
+    controls::{Action, InputContext, InputContexts, StepActions},
+    hud::HudRoot,
+    in_combat,
     player::Player,
-    GameState, TILE_SIZE,
+    rng::{GameRng, RngStream},
+    transition::{Easing, Transition, TransitionEffect, TransitionFinished, Transitions},
+    widgets::{spawn_ascii_bar, spawn_ascii_list, spawn_ascii_panel, AsciiBar, AsciiList},
+    GameState, FIXED_TIME_STEP, FIXED_UPDATE, TILE_SIZE,
 };
 
+const HEALTH_BAR_WIDTH: usize = 10;
+const COMBAT_ACTIONS: [&str; 2] = ["Fight", "Run"];
+
 #[derive(Component)]
 pub struct Enemy;
 
+pub struct EnemyTemplate {
+    pub name: &'static str,
+    pub glyph: char,
+    pub health: isize,
+    pub attack: isize,
+    pub defense: isize,
+    pub speed: isize,
+    pub experience: usize,
+    pub loot: Option<&'static str>,
+    // Bosses can't be run from
+    pub no_escape: bool,
+}
+
+pub const ENEMIES: [EnemyTemplate; 4] = [
+    EnemyTemplate {
+        name: "Bat",
+        glyph: 'b',
+        health: 3,
+        attack: 2,
+        defense: 1,
+        speed: 6,
+        experience: 5,
+        loot: Some("a bat wing"),
+        no_escape: false,
+    },
+    EnemyTemplate {
+        name: "Slime",
+        glyph: 's',
+        health: 4,
+        attack: 1,
+        defense: 0,
+        speed: 1,
+        experience: 3,
+        loot: None,
+        no_escape: false,
+    },
+    EnemyTemplate {
+        name: "Skeleton",
+        glyph: 'S',
+        health: 6,
+        attack: 3,
+        defense: 1,
+        speed: 3,
+        experience: 12,
+        loot: Some("an old bone"),
+        no_escape: false,
+    },
+    EnemyTemplate {
+        name: "Lich",
+        glyph: 'L',
+        health: 12,
+        attack: 3,
+        defense: 2,
+        speed: 2,
+        experience: 40,
+        loot: Some("a cracked crown"),
+        no_escape: true,
+    },
+];
+
+// Which enemy the next battle is against
+#[derive(Resource)]
+pub struct NextEnemy(pub String);
+
+impl Default for NextEnemy {
+    fn default() -> Self {
+        Self(ENEMIES[0].name.to_string())
+    }
+}
+
+pub fn find_enemy(name: &str) -> Option<&'static EnemyTemplate> {
+    ENEMIES
+        .iter()
+        .find(|enemy| enemy.name.eq_ignore_ascii_case(name))
+}
+
 pub struct CombatPlugin;
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+pub enum CombatPhase {
+    Inactive,
+    Intro,
+    Fighting,
+    Outro,
+}
+
+#[derive(Component)]
+pub struct CombatReward {
+    pub experience: usize,
+    pub loot: Option<String>,
+}
+
+#[derive(Component)]
+struct NoEscape;
+
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+enum CombatOutcome {
+    #[default]
+    Victory,
+    Escaped,
+    Defeat,
+}
+
+#[derive(Component)]
+struct CombatMessage;
+
+#[derive(Component)]
+struct CombatMenu;
+
+#[derive(Component)]
+struct CombatLogText;
+
+// Sent when the player has used up their turn, attacking or failing to run
+struct EnemyTurn;
+
+struct CombatLog(String);
+
+#[derive(Resource)]
+struct PhaseTimer(Timer);
+
 pub struct FightEvent {
     target: Entity,
     damege_amount: isize,
@@ -26,102 +155,483 @@ pub struct CombatStats {
     pub max_halth: isize,
     pub attack: isize,
     pub defense: isize,
+    pub speed: isize,
 }
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<FightEvent>()
+        // Battles play out in fixed steps so replays reproduce them exactly. Their events are
+        // kept for two steps rather than two frames, as a frame may run no step at all.
+        app.init_resource::<Events<FightEvent>>()
+            .init_resource::<Events<EnemyTurn>>()
+            .init_resource::<Events<CombatLog>>()
+            .add_system_to_stage(FIXED_UPDATE, Events::<FightEvent>::update_system.at_start())
+            .add_system_to_stage(FIXED_UPDATE, Events::<EnemyTurn>::update_system.at_start())
+            .add_system_to_stage(FIXED_UPDATE, Events::<CombatLog>::update_system.at_start())
+            .init_resource::<NextEnemy>()
+            .init_resource::<CombatOutcome>()
+            .add_state_to_stage(FIXED_UPDATE, CombatPhase::Inactive)
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::new()
+                    .with_run_criteria(in_combat)
+                    .with_system(start_intro.after("transitions"))
+                    .with_system(damage_calculation.after(combat_input))
+                    .with_system(enemy_turn.after(damage_calculation)),
+            )
             .add_system_set(
-                SystemSet::on_update(GameState::Combat)
-                    .with_system(test_exit_combat)
+                SystemSet::on_enter(GameState::Combat)
+                    .with_system(spawn_enemy)
+                    .with_system(start_intro_without_transition),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Combat)
+                    .with_system(despawn_enemy)
+                    .with_system(end_combat_phase),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_enter(CombatPhase::Intro).with_system(spawn_intro),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_update(CombatPhase::Intro).with_system(finish_intro),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_enter(CombatPhase::Fighting).with_system(spawn_combat_menu),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_update(CombatPhase::Fighting)
                     .with_system(combat_input)
-                    .with_system(damage_calculation)
-                    .with_system(combat_camera),
+                    .with_system(update_combat_log.after(enemy_turn)),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_enter(CombatPhase::Outro).with_system(spawn_outro),
             )
-            .add_system_set(SystemSet::on_enter(GameState::Combat).with_system(spawn_enemy))
-            .add_system_set(SystemSet::on_exit(GameState::Combat).with_system(despawn_enemy));
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_update(CombatPhase::Outro).with_system(finish_outro),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_exit(CombatPhase::Intro).with_system(despawn_combat_messages),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_exit(CombatPhase::Fighting).with_system(despawn_combat_messages),
+            )
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::on_exit(CombatPhase::Outro).with_system(despawn_combat_messages),
+            );
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn damage_calculation(
     mut commands: Commands,
     ascii: Res<AsciiSheet>,
     mut fight_event: EventReader<FightEvent>,
     text_query: Query<&AsciiText>,
+    mut bar_query: Query<&mut AsciiBar>,
     mut target_query: Query<(&Children, &mut CombatStats)>,
+    enemy_query: Query<&Enemy>,
+    player_query: Query<&Player>,
+    mut outcome: ResMut<CombatOutcome>,
+    mut phase: ResMut<State<CombatPhase>>,
 ) {
     for event in fight_event.iter() {
         let (target_children, mut target_stats) = target_query
             .get_mut(event.target)
             .expect("Fighting target without stats!");
         target_stats.health = std::cmp::max(
-            target_stats.health - (event.damege_amount - target_stats.defense),
+            target_stats.health - damage_dealt(event.damege_amount, target_stats.defense),
             0,
         );
 
         for child in target_children.iter() {
             if text_query.get(*child).is_ok() {
                 commands.entity(*child).despawn_recursive();
-                let new_health = spawn_ascii_text(
-                    &mut commands,
-                    &ascii,
-                    &format!("Health: {}", target_stats.health),
-                    Vec3 {
-                        x: -4.5 * TILE_SIZE,
-                        y: 2.0 * TILE_SIZE,
-                        z: 100.0,
-                    },
-                );
+                let new_health = spawn_health_text(&mut commands, &ascii, target_stats.health);
 
                 commands.entity(event.target).add_child(new_health);
             }
+            if let Ok(mut bar) = bar_query.get_mut(*child) {
+                bar.fraction = target_stats.health as f32 / target_stats.max_halth as f32;
+            }
+        }
+
+        if target_stats.health > 0 {
+            continue;
+        }
+        if enemy_query.get(event.target).is_ok() {
+            *outcome = CombatOutcome::Victory;
+        } else if player_query.get(event.target).is_ok() {
+            *outcome = CombatOutcome::Defeat;
+        } else {
+            continue;
+        }
+        if let Err(err) = phase.set(CombatPhase::Outro) {
+            warn!("Could not end combat: {:?}", err);
         }
+    }
+}
+
+// Defense soaks up damage but never turns a hit into healing
+fn damage_dealt(attack: isize, defense: isize) -> isize {
+    std::cmp::max(attack - defense, 0)
+}
+
+// Even speeds are a coin flip, each point of difference shifts the odds by a tenth
+pub fn escape_chance(runner_speed: isize, chaser_speed: isize) -> f32 {
+    (0.5 + 0.1 * (runner_speed - chaser_speed) as f32).clamp(0.1, 0.95)
+}
+
+fn start_intro(
+    mut finished_event: EventReader<TransitionFinished>,
+    mut phase: ResMut<State<CombatPhase>>,
+) {
+    for event in finished_event.iter() {
+        if event.next_state == GameState::Combat && phase.current() == &CombatPhase::Inactive {
+            if let Err(err) = phase.set(CombatPhase::Intro) {
+                warn!("Could not start combat intro: {:?}", err);
+            }
+        }
+    }
+}
 
-        if target_stats.health == 0 {
-            create_fadeout(&mut commands, GameState::Overworld, &ascii)
+// Entering combat directly (e.g. from the command line) has no transition to wait for
+fn start_intro_without_transition(
+    transitions: Res<Transitions>,
+    mut phase: ResMut<State<CombatPhase>>,
+) {
+    if !transitions.is_running() && phase.current() == &CombatPhase::Inactive {
+        // The phase only changes in fixed steps, and when starting straight into combat
+        // none has run yet to finish its initial transition, which set would refuse
+        if let Err(err) = phase.overwrite_set(CombatPhase::Intro) {
+            warn!("Could not start combat intro: {:?}", err);
         }
     }
 }
 
+fn spawn_intro(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    hud_query: Query<Entity, With<HudRoot>>,
+    enemy_query: Query<&Name, With<Enemy>>,
+) {
+    let name = enemy_query
+        .iter()
+        .next()
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| "Enemy".to_string());
+    let banner = spawn_ascii_panel(
+        &mut commands,
+        &ascii,
+        Vec3::new(0.0, 6.0 * TILE_SIZE, 500.0),
+        name.chars().count() + 4,
+        3,
+        None,
+    );
+    let banner_text = spawn_combat_text(&mut commands, &ascii, &name, 0.0, 2.0);
+    commands.entity(banner).add_child(banner_text);
+
+    let message = spawn_combat_text(
+        &mut commands,
+        &ascii,
+        &format!("A wild {} appears!", name),
+        -4.0 * TILE_SIZE,
+        500.0,
+    );
+
+    commands.entity(banner).insert(CombatMessage);
+    commands.entity(message).insert(CombatMessage);
+    commands
+        .entity(hud_query.single())
+        .push_children(&[banner, message]);
+    commands.insert_resource(PhaseTimer(Timer::from_seconds(2.0, TimerMode::Once)));
+}
+
+fn finish_intro(mut timer: ResMut<PhaseTimer>, mut phase: ResMut<State<CombatPhase>>) {
+    timer.0.tick(Duration::from_secs_f32(FIXED_TIME_STEP));
+    if timer.0.just_finished() {
+        if let Err(err) = phase.set(CombatPhase::Fighting) {
+            warn!("Could not start fighting: {:?}", err);
+        }
+    }
+}
+
+fn spawn_outro(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    outcome: Res<CombatOutcome>,
+    hud_query: Query<Entity, With<HudRoot>>,
+    enemy_query: Query<&CombatReward, With<Enemy>>,
+    mut player_query: Query<(&mut Player, &mut CombatStats)>,
+) {
+    let summary = match *outcome {
+        CombatOutcome::Victory => {
+            let mut summary = vec!["Victory!".to_string()];
+            if let Some(reward) = enemy_query.iter().next() {
+                summary.push(format!("Gained {} XP", reward.experience));
+                if let Some(loot) = &reward.loot {
+                    summary.push(format!("Found {}", loot));
+                }
+                if let Ok((mut player, _)) = player_query.get_single_mut() {
+                    player.experience += reward.experience;
+                }
+            }
+            summary
+        }
+        CombatOutcome::Escaped => vec!["Got away safely!".to_string()],
+        CombatOutcome::Defeat => {
+            // There is no game over yet, the player comes round back on the map
+            if let Ok((_, mut stats)) = player_query.get_single_mut() {
+                stats.health = stats.max_halth;
+            }
+            vec![
+                "You were defeated...".to_string(),
+                "You wake up, bruised".to_string(),
+            ]
+        }
+    };
+
+    let message = spawn_ascii_text_aligned(
+        &mut commands,
+        &ascii,
+        &summary.join("\n"),
+        Vec3::new(0.0, -4.0 * TILE_SIZE, 500.0),
+        TextLayout::new(HorizontalAlign::Center, VerticalAlign::Top).with_line_spacing(1.5),
+    );
+    commands.entity(message).insert(CombatMessage);
+    commands.entity(hud_query.single()).add_child(message);
+    commands.insert_resource(PhaseTimer(Timer::from_seconds(2.5, TimerMode::Once)));
+}
+
+fn finish_outro(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    mut timer: ResMut<PhaseTimer>,
+    mut transitions: ResMut<Transitions>,
+) {
+    timer.0.tick(Duration::from_secs_f32(FIXED_TIME_STEP));
+    // Keeps trying while another transition is running; one leaving combat ends the outro
+    // before this gets another go
+    if timer.0.finished() && !transitions.is_running() {
+        let transition = Transition::new(TransitionEffect::Iris, GameState::Overworld)
+            .with_easing(Easing::EaseInOut);
+        if let Err(err) = transitions.start(&mut commands, &ascii, transition) {
+            warn!("Could not leave combat: {}", err);
+        }
+    }
+}
+
+fn despawn_combat_messages(
+    mut commands: Commands,
+    message_query: Query<Entity, With<CombatMessage>>,
+) {
+    for entity in message_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn end_combat_phase(mut phase: ResMut<State<CombatPhase>>) {
+    if phase.current() != &CombatPhase::Inactive {
+        if let Err(err) = phase.set(CombatPhase::Inactive) {
+            warn!("Could not reset combat phase: {:?}", err);
+        }
+    }
+}
+
+fn spawn_combat_text(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    text: &str,
+    y: f32,
+    z: f32,
+) -> Entity {
+    spawn_ascii_text_aligned(
+        commands,
+        ascii,
+        text,
+        Vec3::new(0.0, y, z),
+        TextLayout::new(HorizontalAlign::Center, VerticalAlign::Middle),
+    )
+}
+
+fn spawn_combat_menu(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    hud_query: Query<Entity, With<HudRoot>>,
+) {
+    let menu = spawn_ascii_list(
+        &mut commands,
+        &ascii,
+        Vec3::new(-3.0 * TILE_SIZE, -4.0 * TILE_SIZE, 500.0),
+        &COMBAT_ACTIONS,
+    );
+    commands
+        .entity(menu)
+        .insert(CombatMenu)
+        .insert(CombatMessage);
+    commands.entity(hud_query.single()).add_child(menu);
+}
+
+#[allow(clippy::too_many_arguments)]
 fn combat_input(
-    keyboard: Res<Input<KeyCode>>,
+    mut actions: ResMut<StepActions>,
+    contexts: Res<InputContexts>,
+    mut rng: ResMut<GameRng>,
+    mut outcome: ResMut<CombatOutcome>,
+    mut phase: ResMut<State<CombatPhase>>,
     mut fight_event: EventWriter<FightEvent>,
+    mut turn_event: EventWriter<EnemyTurn>,
+    mut log_event: EventWriter<CombatLog>,
+    mut menu_query: Query<&mut AsciiList, With<CombatMenu>>,
     player_query: Query<&CombatStats, With<Player>>,
-    enemy_query: Query<Entity, With<Enemy>>,
+    enemy_query: Query<(Entity, &Name, &CombatStats, Option<&NoEscape>), With<Enemy>>,
 ) {
+    if !contexts.is_active(InputContext::Combat) {
+        return;
+    }
+    let mut menu = match menu_query.get_single_mut() {
+        Ok(menu) => menu,
+        Err(_) => return,
+    };
+
+    if actions.just_pressed(Action::MoveUp) {
+        menu.select_previous();
+        return;
+    } else if actions.just_pressed(Action::MoveDown) {
+        menu.select_next();
+        return;
+    } else if !actions.clear_just_pressed(Action::Confirm) {
+        return;
+    }
+
     let player_stats = player_query.single();
-    let target = enemy_query.iter().next().unwrap();
-    if keyboard.just_pressed(KeyCode::Return) {
+    let (target, name, enemy_stats, no_escape) = enemy_query.iter().next().unwrap();
+    match menu.selected_item() {
+        Some("Fight") => {
+            fight_event.send(FightEvent {
+                target,
+                damege_amount: player_stats.attack,
+            });
+            log_event.send(CombatLog(format!(
+                "You hit the {} for {}",
+                name,
+                damage_dealt(player_stats.attack, enemy_stats.defense)
+            )));
+            turn_event.send(EnemyTurn);
+        }
+        Some("Run") => {
+            // Trying is free against a boss, there was never a chance
+            if no_escape.is_some() {
+                log_event.send(CombatLog(format!("The {} blocks the way!", name)));
+            } else if rng
+                .stream(RngStream::Combat)
+                .chance(escape_chance(player_stats.speed, enemy_stats.speed))
+            {
+                *outcome = CombatOutcome::Escaped;
+                if let Err(err) = phase.set(CombatPhase::Outro) {
+                    warn!("Could not run from combat: {:?}", err);
+                }
+            } else {
+                log_event.send(CombatLog("Couldn't get away!".to_string()));
+                turn_event.send(EnemyTurn);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn enemy_turn(
+    mut turn_event: EventReader<EnemyTurn>,
+    mut fight_event: EventWriter<FightEvent>,
+    mut log_event: EventWriter<CombatLog>,
+    player_query: Query<(Entity, &CombatStats), With<Player>>,
+    enemy_query: Query<(&Name, &CombatStats), With<Enemy>>,
+) {
+    for _ in turn_event.iter() {
+        let (player, player_stats) = player_query.single();
+        let (name, enemy_stats) = match enemy_query.get_single() {
+            Ok(enemy) => enemy,
+            Err(_) => continue,
+        };
+        if enemy_stats.health == 0 {
+            continue;
+        }
+        let damage = damage_dealt(enemy_stats.attack, player_stats.defense);
         fight_event.send(FightEvent {
-            target: target,
-            damege_amount: player_stats.attack,
+            target: player,
+            damege_amount: enemy_stats.attack,
         });
+        log_event.send(CombatLog(format!(
+            "The {} hits you for {} (HP {}/{})",
+            name,
+            damage,
+            std::cmp::max(player_stats.health - damage, 0),
+            player_stats.max_halth
+        )));
     }
 }
 
-fn combat_camera(mut camera_query: Query<&mut Transform, With<Camera>>) {
-    let mut camera_transform = camera_query.single_mut();
-    camera_transform.translation.x = 0.0;
-    camera_transform.translation.y = 0.0;
+// Shows everything that happened in the last turn, replacing the turn before
+fn update_combat_log(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    mut log_event: EventReader<CombatLog>,
+    hud_query: Query<Entity, With<HudRoot>>,
+    text_query: Query<Entity, With<CombatLogText>>,
+) {
+    let lines: Vec<&str> = log_event.iter().map(|log| log.0.as_str()).collect();
+    if lines.is_empty() {
+        return;
+    }
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let text = spawn_ascii_text_aligned(
+        &mut commands,
+        &ascii,
+        &lines.join("\n"),
+        Vec3::new(0.0, -7.0 * TILE_SIZE, 500.0),
+        TextLayout::new(HorizontalAlign::Center, VerticalAlign::Top).with_line_spacing(1.5),
+    );
+    commands
+        .entity(text)
+        .insert(CombatLogText)
+        .insert(CombatMessage);
+    commands.entity(hud_query.single()).add_child(text);
 }
 
-fn spawn_enemy(mut commands: Commands, ascii: Res<AsciiSheet>) {
-    let enemt_health = 3;
-    let heath_text = spawn_ascii_text(
+fn spawn_enemy(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    next_enemy: Res<NextEnemy>,
+    hud_query: Query<Entity, With<HudRoot>>,
+) {
+    let template = find_enemy(&next_enemy.0).unwrap_or_else(|| {
+        warn!("Unknown enemy {}", next_enemy.0);
+        &ENEMIES[0]
+    });
+    let heath_text = spawn_health_text(&mut commands, &ascii, template.health);
+    let health_bar = spawn_ascii_bar(
         &mut commands,
         &ascii,
-        &format!("Health: {}", enemt_health),
-        Vec3 {
-            x: -4.5 * TILE_SIZE,
-            y: 2.0 * TILE_SIZE,
-            z: 100.0,
-        },
+        Vec3::new(0.0, 1.0 * TILE_SIZE, 100.0),
+        HEALTH_BAR_WIDTH,
+        1.0,
+        Color::rgb(0.8, 0.2, 0.2),
     );
 
-    let sprite = spawn_ascii_sprite(
+    let sprite = spawn_ascii_char(
         &mut commands,
         &ascii,
-        'b' as usize,
+        template.glyph,
         Color::rgb(0.8, 0.8, 0.8),
         Vec3::new(0.0, 0.0, 100.0),
         Vec3::splat(1.0),
@@ -131,13 +641,35 @@ fn spawn_enemy(mut commands: Commands, ascii: Res<AsciiSheet>) {
         .entity(sprite)
         .insert(Enemy)
         .insert(CombatStats {
-            health: enemt_health,
-            max_halth: enemt_health,
-            attack: 2,
-            defense: 1,
+            health: template.health,
+            max_halth: template.health,
+            attack: template.attack,
+            defense: template.defense,
+            speed: template.speed,
+        })
+        .insert(Name::new(template.name))
+        .insert(CombatReward {
+            experience: template.experience,
+            loot: template.loot.map(|loot| loot.to_string()),
         })
-        .insert(Name::new("Bat"))
-        .add_child(heath_text);
+        .add_child(heath_text)
+        .add_child(health_bar);
+    if template.no_escape {
+        commands.entity(sprite).insert(NoEscape);
+    }
+    commands.insert_resource(CombatOutcome::default());
+
+    commands.entity(hud_query.single()).add_child(sprite);
+}
+
+fn spawn_health_text(commands: &mut Commands, ascii: &AsciiSheet, health: isize) -> Entity {
+    spawn_combat_text(
+        commands,
+        ascii,
+        &format!("Health: {}", health),
+        2.0 * TILE_SIZE,
+        100.0,
+    )
 }
 
 fn despawn_enemy(mut commands: Commands, enemy_query: Query<Entity, With<Enemy>>) {
@@ -146,10 +678,20 @@ fn despawn_enemy(mut commands: Commands, enemy_query: Query<Entity, With<Enemy>>
     }
 }
 
-fn test_exit_combat(mut keyboard: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
-    if keyboard.just_pressed(KeyCode::Space) {
-        println!("Changing to Overworld");
-        state.set(GameState::Overworld).unwrap();
-        keyboard.clear();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faster_runners_escape_more_often() {
+        assert_eq!(escape_chance(4, 4), 0.5);
+        assert!(escape_chance(6, 2) > escape_chance(4, 4));
+        assert!(escape_chance(2, 6) < escape_chance(4, 4));
+    }
+
+    #[test]
+    fn escape_is_never_certain_either_way() {
+        assert_eq!(escape_chance(20, 1), 0.95);
+        assert_eq!(escape_chance(1, 20), 0.1);
     }
 }
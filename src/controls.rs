@@ -0,0 +1,401 @@
+use std::{fs, path::Path};
+
+use bevy::{input::InputSystem, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{replay::ReplayPlayer, transition::TransitionFinished, GameState, FIXED_UPDATE};
+
+pub const INPUT_CONFIG_PATH: &str = "config/input.ron";
+
+pub struct ControlsPlugin;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Confirm,
+    Cancel,
+    Menu,
+    Pause,
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct InputBindings {
+    pub keyboard: HashMap<Action, Vec<KeyCode>>,
+    #[serde(default)]
+    pub gamepad: HashMap<Action, Vec<GamepadButtonType>>,
+    #[serde(default = "default_stick_dead_zone")]
+    pub stick_dead_zone: f32,
+}
+
+// Actions as the fixed step sees them. A tap between two steps stays pressed until a step
+// has run, however fast frames are rendered.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct StepActions(pub Input<Action>);
+
+// The gamepad that drives actions; replaced when it is unplugged
+#[derive(Resource, Default)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+fn default_stick_dead_zone() -> f32 {
+    0.5
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputContext {
+    Overworld,
+    Menu,
+    Combat,
+    Transition,
+}
+
+// Only the context on top of the stack receives input
+#[derive(Resource)]
+pub struct InputContexts {
+    stack: Vec<InputContext>,
+}
+
+impl Default for InputContexts {
+    fn default() -> Self {
+        Self {
+            stack: vec![InputContext::Overworld],
+        }
+    }
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Menu,
+        Action::Pause,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move Up",
+            Action::MoveDown => "Move Down",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Confirm => "Confirm",
+            Action::Cancel => "Cancel",
+            Action::Menu => "Menu",
+            Action::Pause => "Pause",
+        }
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keyboard = [
+            (Action::MoveUp, vec![KeyCode::W, KeyCode::Up]),
+            (Action::MoveDown, vec![KeyCode::S, KeyCode::Down]),
+            (Action::MoveLeft, vec![KeyCode::A, KeyCode::Left]),
+            (Action::MoveRight, vec![KeyCode::D, KeyCode::Right]),
+            (Action::Confirm, vec![KeyCode::Return, KeyCode::Z]),
+            (Action::Cancel, vec![KeyCode::Escape, KeyCode::X]),
+            (Action::Menu, vec![KeyCode::Tab]),
+            (Action::Pause, vec![KeyCode::Escape, KeyCode::P]),
+        ];
+        let gamepad = [
+            (Action::MoveUp, vec![GamepadButtonType::DPadUp]),
+            (Action::MoveDown, vec![GamepadButtonType::DPadDown]),
+            (Action::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+            (Action::MoveRight, vec![GamepadButtonType::DPadRight]),
+            (Action::Confirm, vec![GamepadButtonType::South]),
+            (Action::Cancel, vec![GamepadButtonType::East]),
+            (Action::Menu, vec![GamepadButtonType::Select]),
+            (Action::Pause, vec![GamepadButtonType::Start]),
+        ];
+        Self {
+            keyboard: keyboard.into_iter().collect(),
+            gamepad: gamepad.into_iter().collect(),
+            stick_dead_zone: default_stick_dead_zone(),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut bindings: InputBindings =
+            ron::from_str(&contents).map_err(|err| err.to_string())?;
+
+        // Actions added since the file was written keep their default bindings
+        let defaults = InputBindings::default();
+        for (action, keys) in defaults.keyboard {
+            bindings.keyboard.entry(action).or_insert(keys);
+        }
+        for (action, buttons) in defaults.gamepad {
+            bindings.gamepad.entry(action).or_insert(buttons);
+        }
+        Ok(bindings)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keyboard
+            .get(&action)
+            .map(|keys| keys.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.gamepad
+            .get(&action)
+            .map(|buttons| buttons.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn rebind(&mut self, action: Action, key: KeyCode) {
+        self.keyboard.insert(action, vec![key]);
+    }
+
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .keys(action)
+            .iter()
+            .map(|key| format!("{:?}", key))
+            .collect();
+        if keys.is_empty() {
+            "-".to_string()
+        } else {
+            keys.join(" / ")
+        }
+    }
+}
+
+impl InputContexts {
+    pub fn active(&self) -> Option<InputContext> {
+        self.stack.last().copied()
+    }
+
+    pub fn is_active(&self, context: InputContext) -> bool {
+        self.active() == Some(context)
+    }
+
+    pub fn push(&mut self, context: InputContext) {
+        self.stack.push(context);
+    }
+
+    pub fn pop(&mut self, context: InputContext) {
+        if let Some(index) = self.stack.iter().rposition(|&c| c == context) {
+            self.stack.remove(index);
+        }
+    }
+
+    pub fn set_base(&mut self, context: InputContext) {
+        match self.stack.first_mut() {
+            Some(base) => *base = context,
+            None => self.stack.push(context),
+        }
+    }
+}
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputContexts>()
+            .init_resource::<InputBindings>()
+            .init_resource::<Input<Action>>()
+            .init_resource::<StepActions>()
+            .init_resource::<ActiveGamepad>()
+            .add_startup_system(load_bindings)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                track_gamepads.after(InputSystem).before(update_actions),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_actions.label("actions").after(InputSystem),
+            )
+            .add_system_to_stage(CoreStage::PreUpdate, update_step_actions.after("actions"))
+            .add_system_to_stage(FIXED_UPDATE, clear_step_actions.at_end())
+            .add_system_to_stage(FIXED_UPDATE, track_transitions.after("transitions"))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Overworld).with_system(enter_overworld_context),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Combat).with_system(enter_combat_context),
+            );
+    }
+}
+
+fn load_bindings(mut commands: Commands) {
+    let bindings = InputBindings::load(INPUT_CONFIG_PATH).unwrap_or_else(|err| {
+        info!(
+            "Using default key bindings ({}: {})",
+            INPUT_CONFIG_PATH, err
+        );
+        InputBindings::default()
+    });
+    commands.insert_resource(bindings);
+}
+
+fn track_gamepads(
+    mut gamepad_event: EventReader<GamepadEvent>,
+    gamepads: Res<Gamepads>,
+    mut active: ResMut<ActiveGamepad>,
+) {
+    for event in gamepad_event.iter() {
+        match event.event_type {
+            GamepadEventType::Connected(_) if active.0.is_none() => {
+                info!("Using {:?} for input", event.gamepad);
+                active.0 = Some(event.gamepad);
+            }
+            GamepadEventType::Disconnected if active.0 == Some(event.gamepad) => {
+                active.0 = gamepads.iter().find(|&gamepad| gamepad != event.gamepad);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_actions(
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    active_gamepad: Res<ActiveGamepad>,
+    bindings: Res<InputBindings>,
+    mut actions: ResMut<Input<Action>>,
+) {
+    actions.clear();
+    for action in Action::ALL {
+        let mut pressed = bindings
+            .keys(action)
+            .iter()
+            .any(|key| keyboard.pressed(*key));
+
+        if let Some(gamepad) = active_gamepad.0 {
+            pressed |= bindings
+                .buttons(action)
+                .iter()
+                .any(|button| buttons.pressed(GamepadButton::new(gamepad, *button)));
+            pressed |= stick_pressed(&axes, gamepad, action, bindings.stick_dead_zone);
+        }
+
+        if pressed {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
+
+// A replay presses the step actions itself
+fn update_step_actions(
+    actions: Res<Input<Action>>,
+    mut step_actions: ResMut<StepActions>,
+    replay_player: Option<Res<ReplayPlayer>>,
+) {
+    if replay_player.is_some() {
+        return;
+    }
+    for action in Action::ALL {
+        if actions.pressed(action) {
+            step_actions.press(action);
+        } else if !step_actions.just_pressed(action) {
+            step_actions.release(action);
+        }
+    }
+}
+
+fn clear_step_actions(mut step_actions: ResMut<StepActions>) {
+    step_actions.clear();
+}
+
+fn stick_pressed(
+    axes: &Axis<GamepadAxis>,
+    gamepad: Gamepad,
+    action: Action,
+    dead_zone: f32,
+) -> bool {
+    let axis = |axis_type| {
+        axes.get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or(0.0)
+    };
+    match action {
+        Action::MoveUp => axis(GamepadAxisType::LeftStickY) > dead_zone,
+        Action::MoveDown => axis(GamepadAxisType::LeftStickY) < -dead_zone,
+        Action::MoveLeft => axis(GamepadAxisType::LeftStickX) < -dead_zone,
+        Action::MoveRight => axis(GamepadAxisType::LeftStickX) > dead_zone,
+        _ => false,
+    }
+}
+
+fn enter_overworld_context(mut contexts: ResMut<InputContexts>) {
+    contexts.set_base(InputContext::Overworld);
+}
+
+fn enter_combat_context(mut contexts: ResMut<InputContexts>) {
+    contexts.set_base(InputContext::Combat);
+}
+
+// The Transition context is pushed by Transitions::start itself
+fn track_transitions(
+    mut contexts: ResMut<InputContexts>,
+    mut finished_event: EventReader<TransitionFinished>,
+) {
+    for _ in finished_event.iter() {
+        contexts.pop(InputContext::Transition);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_top_context_is_active() {
+        let mut contexts = InputContexts::default();
+        assert!(contexts.is_active(InputContext::Overworld));
+
+        contexts.push(InputContext::Transition);
+        assert!(!contexts.is_active(InputContext::Overworld));
+
+        contexts.set_base(InputContext::Combat);
+        contexts.pop(InputContext::Transition);
+        assert!(contexts.is_active(InputContext::Combat));
+    }
+
+    #[test]
+    fn pop_removes_topmost_matching_context() {
+        let mut contexts = InputContexts::default();
+        contexts.push(InputContext::Menu);
+        contexts.push(InputContext::Transition);
+        contexts.pop(InputContext::Menu);
+        assert_eq!(
+            contexts.stack,
+            vec![InputContext::Overworld, InputContext::Transition]
+        );
+
+        contexts.pop(InputContext::Menu);
+        assert_eq!(contexts.active(), Some(InputContext::Transition));
+    }
+
+    #[test]
+    fn stick_respects_dead_zone() {
+        let gamepad = Gamepad::new(0);
+        let mut axes = Axis::<GamepadAxis>::default();
+        axes.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX), -0.3);
+        axes.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY), 0.8);
+
+        assert!(!stick_pressed(&axes, gamepad, Action::MoveLeft, 0.5));
+        assert!(stick_pressed(&axes, gamepad, Action::MoveUp, 0.5));
+        assert!(!stick_pressed(&axes, gamepad, Action::MoveDown, 0.5));
+        assert!(!stick_pressed(&axes, gamepad, Action::Confirm, 0.5));
+    }
+}
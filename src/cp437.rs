@@ -0,0 +1,193 @@
+use bevy::utils::HashMap;
+
+// Glyph order of the code page 437 sheet, indexed by tile.
+const CP437: [char; 256] = [
+    '\0', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼', //
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼', //
+    ' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', //
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', //
+    '@', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', //
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '[', '\\', ']', '^', '_', //
+    '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', //
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~', '⌂', //
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+pub const FALLBACK_GLYPH: usize = '?' as usize;
+
+#[derive(Clone, Debug)]
+pub struct Cp437Mapping {
+    overrides: HashMap<char, usize>,
+    pub fallback: usize,
+}
+
+impl Default for Cp437Mapping {
+    fn default() -> Self {
+        Self {
+            overrides: HashMap::default(),
+            fallback: FALLBACK_GLYPH,
+        }
+    }
+}
+
+impl Cp437Mapping {
+    pub fn with_override(mut self, char: char, index: usize) -> Self {
+        self.overrides.insert(char, index);
+        self
+    }
+
+    pub fn with_fallback(mut self, index: usize) -> Self {
+        self.fallback = index;
+        self
+    }
+
+    pub fn get(&self, char: char) -> Option<usize> {
+        if let Some(index) = self.overrides.get(&char) {
+            return Some(*index);
+        }
+        unicode_to_cp437(char).or_else(|| approximate(char).and_then(unicode_to_cp437))
+    }
+
+    pub fn index_of(&self, char: char) -> usize {
+        self.get(char).unwrap_or(self.fallback)
+    }
+}
+
+pub fn unicode_to_cp437(char: char) -> Option<usize> {
+    // Plain ASCII (including control codes used as tile indices) maps to itself
+    if (char as u32) < 128 {
+        return Some(char as usize);
+    }
+    CP437.iter().position(|&glyph| glyph == char)
+}
+
+// Closest glyph for characters the code page has no exact match for
+fn approximate(char: char) -> Option<char> {
+    let approximation = match char {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ā' | 'Ă' | 'Ą' => 'A',
+        'ã' | 'ā' | 'ă' | 'ą' => 'a',
+        'Ć' | 'Ĉ' | 'Ċ' | 'Č' => 'C',
+        'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'Ď' | 'Đ' | 'Ð' => 'D',
+        'ď' | 'đ' | 'ð' => 'd',
+        'È' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => 'E',
+        'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => 'G',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Į' | 'İ' => 'I',
+        'ĩ' | 'ī' | 'į' | 'ı' => 'i',
+        'Ł' | 'Ĺ' | 'Ľ' => 'L',
+        'ł' | 'ĺ' | 'ľ' => 'l',
+        'Ń' | 'Ň' => 'N',
+        'ń' | 'ň' => 'n',
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' | 'Ō' | 'Ő' => 'O',
+        'õ' | 'ø' | 'ō' | 'ő' => 'o',
+        'Œ' => 'O',
+        'œ' => 'o',
+        'Ŕ' | 'Ř' => 'R',
+        'ŕ' | 'ř' => 'r',
+        'Ś' | 'Ş' | 'Š' => 'S',
+        'ś' | 'ş' | 'š' => 's',
+        'Ţ' | 'Ť' => 'T',
+        'ţ' | 'ť' => 't',
+        'Ù' | 'Ú' | 'Û' | 'Ũ' | 'Ū' | 'Ů' | 'Ű' | 'Ų' => 'U',
+        'ũ' | 'ū' | 'ů' | 'ű' | 'ų' => 'u',
+        'Ý' | 'Ÿ' => 'Y',
+        'ý' => 'y',
+        'Ź' | 'Ż' | 'Ž' => 'Z',
+        'ź' | 'ż' | 'ž' => 'z',
+        '‘' | '’' | '‚' | '′' => '\'',
+        '“' | '”' | '„' | '″' => '"',
+        '‐' | '‑' | '–' | '—' | '−' => '-',
+        '…' => '.',
+        '×' => 'x',
+        '╭' => '┌',
+        '╮' => '┐',
+        '╯' => '┘',
+        '╰' => '└',
+        '━' | '╌' | '┄' => '─',
+        '┃' | '╎' | '┆' => '│',
+        '┏' => '┌',
+        '┓' => '┐',
+        '┗' => '└',
+        '┛' => '┘',
+        '┣' => '├',
+        '┫' => '┤',
+        '┳' => '┬',
+        '┻' => '┴',
+        '╋' => '┼',
+        '▪' | '◼' => '■',
+        '€' => 'E',
+        'μ' => 'µ',
+        'β' => 'ß',
+        _ => return None,
+    };
+    Some(approximation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_maps_to_itself() {
+        for char in (0u8..128).map(char::from) {
+            assert_eq!(unicode_to_cp437(char), Some(char as usize));
+        }
+    }
+
+    #[test]
+    fn table_round_trips() {
+        for (index, &char) in CP437.iter().enumerate() {
+            assert_eq!(unicode_to_cp437(char), Some(index), "glyph {:?}", char);
+        }
+    }
+
+    #[test]
+    fn accented_latin() {
+        let mapping = Cp437Mapping::default();
+        assert_eq!(mapping.index_of('é'), 0x82);
+        assert_eq!(mapping.index_of('Ñ'), 0xA5);
+        assert_eq!(mapping.index_of('ß'), 0xE1);
+        assert_eq!(mapping.index_of('ã'), 'a' as usize);
+        assert_eq!(mapping.index_of('Ø'), 'O' as usize);
+    }
+
+    #[test]
+    fn box_drawing() {
+        let mapping = Cp437Mapping::default();
+        assert_eq!(mapping.index_of('┌'), 0xDA);
+        assert_eq!(mapping.index_of('═'), 0xCD);
+        assert_eq!(mapping.index_of('█'), 0xDB);
+        assert_eq!(mapping.index_of('╭'), 0xDA);
+        assert_eq!(mapping.index_of('━'), 0xC4);
+    }
+
+    #[test]
+    fn unmappable_uses_fallback() {
+        let mapping = Cp437Mapping::default();
+        assert_eq!(mapping.get('🦇'), None);
+        assert_eq!(mapping.index_of('🦇'), FALLBACK_GLYPH);
+        assert_eq!(mapping.index_of('漢'), FALLBACK_GLYPH);
+
+        let mapping = mapping.with_fallback(0xFE);
+        assert_eq!(mapping.index_of('🦇'), 0xFE);
+    }
+
+    #[test]
+    fn overrides_take_priority() {
+        let mapping = Cp437Mapping::default()
+            .with_override('🦇', 'b' as usize)
+            .with_override('a', 0x85);
+        assert_eq!(mapping.index_of('🦇'), 'b' as usize);
+        assert_eq!(mapping.index_of('a'), 0x85);
+        assert_eq!(mapping.index_of('b'), 'b' as usize);
+    }
+}
@@ -1,16 +1,97 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::{RegisterInspectable, WorldInspectorPlugin};
 
-use crate::player::{EncounterTrackrer, Player};
+use crate::{
+    ascii::{spawn_ascii_text, AsciiSheet, GlyphSheets, SwitchGlyphSheet},
+    controls::{InputContext, InputContexts},
+    encounter::EncounterTracker,
+    hud::{HudAnchor, HudRoot},
+    player::{MovementMode, Player},
+    replay::not_replaying,
+    rng::GameRng,
+    transition::{Transition, Transitions},
+    GameState, TILE_SIZE,
+};
 
 pub struct DebugPlugin;
 
+#[derive(Component)]
+struct DebugOverlay;
+
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
         if cfg!(debug_assertions) {
             app.add_plugin(WorldInspectorPlugin::default())
-                .register_type::<EncounterTrackrer>()
-                .register_inspectable::<Player>();
+                .register_type::<EncounterTracker>()
+                .register_inspectable::<Player>()
+                .add_startup_system(spawn_debug_overlay)
+                .add_system(cycle_glyph_sheet)
+                .add_system(toggle_movement_mode.with_run_criteria(not_replaying))
+                .add_system(skip_combat.with_run_criteria(not_replaying));
+        }
+    }
+}
+
+fn spawn_debug_overlay(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    rng: Res<GameRng>,
+    hud_query: Query<Entity, With<HudRoot>>,
+) {
+    let seed = spawn_ascii_text(
+        &mut commands,
+        &ascii,
+        &format!("seed {}", rng.seed()),
+        Vec3::new(0.0, 0.0, 960.0),
+    );
+    commands
+        .entity(seed)
+        .insert(HudAnchor {
+            anchor: Vec2::new(-1.0, 1.0),
+            offset: Vec2::new(TILE_SIZE / 2.0, -TILE_SIZE),
+        })
+        .insert(Name::new("DebugOverlay"))
+        .insert(DebugOverlay);
+    commands.entity(hud_query.single()).add_child(seed);
+}
+
+fn cycle_glyph_sheet(
+    keyboard: Res<Input<KeyCode>>,
+    sheets: Res<GlyphSheets>,
+    mut switch_event: EventWriter<SwitchGlyphSheet>,
+) {
+    if keyboard.just_pressed(KeyCode::F2) {
+        let next = (sheets.active + 1) % sheets.sheets.len();
+        switch_event.send(SwitchGlyphSheet(sheets.sheets[next].name.clone()));
+    }
+}
+
+fn toggle_movement_mode(keyboard: Res<Input<KeyCode>>, mut mode: ResMut<MovementMode>) {
+    if keyboard.just_pressed(KeyCode::F3) {
+        *mode = match *mode {
+            MovementMode::Continuous => MovementMode::Grid,
+            MovementMode::Grid => MovementMode::Continuous,
+        };
+        info!("Movement mode: {:?}", *mode);
+    }
+}
+
+// Leaves a battle without fighting or rolling to run. The Combat context is only the
+// active one during a battle.
+fn skip_combat(
+    mut commands: Commands,
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut transitions: ResMut<Transitions>,
+    contexts: Res<InputContexts>,
+    ascii: Res<AsciiSheet>,
+) {
+    if contexts.is_active(InputContext::Combat) && keyboard.clear_just_pressed(KeyCode::Space) {
+        if let Err(err) = transitions.start(
+            &mut commands,
+            &ascii,
+            Transition::fade(GameState::Overworld),
+        ) {
+            warn!("Could not skip combat: {}", err);
         }
     }
 }
@@ -0,0 +1,154 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    ascii::AsciiSheet,
+    in_overworld,
+    player::{wall_collision_check, Player, PlayerStepped},
+    rng::{GameRng, Rng, RngStream},
+    tilemap::EncounterSpawner,
+    transition::{Easing, Transition, TransitionEffect, Transitions},
+    GameState, FIXED_UPDATE,
+};
+
+pub struct EncounterPlugin;
+
+#[derive(Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct EncounterTracker {
+    pub steps_since_battle: u32,
+}
+
+#[derive(Resource)]
+pub struct EncounterSettings {
+    // Steps after a battle during which no encounter can happen
+    pub grace_steps: u32,
+    // Chance of a battle per step taken on each kind of encounter tile, by map glyph
+    pub zone_rates: HashMap<char, f32>,
+}
+
+impl Default for EncounterSettings {
+    fn default() -> Self {
+        Self {
+            grace_steps: 4,
+            zone_rates: HashMap::from_iter([('~', 0.12), ('"', 0.06)]),
+        }
+    }
+}
+
+impl EncounterSettings {
+    pub fn zone_rate(&self, glyph: char) -> Option<f32> {
+        self.zone_rates.get(&glyph).copied()
+    }
+}
+
+impl EncounterTracker {
+    pub fn step(&mut self, settings: &EncounterSettings, rng: &mut Rng, rate: f32) -> bool {
+        self.steps_since_battle = self.steps_since_battle.saturating_add(1);
+        if self.steps_since_battle <= settings.grace_steps {
+            return false;
+        }
+        if rng.chance(rate) {
+            self.steps_since_battle = 0;
+            return true;
+        }
+        false
+    }
+}
+
+impl Plugin for EncounterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EncounterSettings>()
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::new()
+                    .with_run_criteria(in_overworld)
+                    .with_system(check_encounters.after("movement")),
+            );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_encounters(
+    mut commands: Commands,
+    mut tracker_query: Query<&mut EncounterTracker, With<Player>>,
+    zone_query: Query<(&Transform, &EncounterSpawner)>,
+    mut stepped_event: EventReader<PlayerStepped>,
+    mut transitions: ResMut<Transitions>,
+    mut rng: ResMut<GameRng>,
+    settings: Res<EncounterSettings>,
+    ascii: Res<AsciiSheet>,
+) {
+    let mut tracker = tracker_query.single_mut();
+
+    for stepped in stepped_event.iter() {
+        if transitions.is_running() {
+            continue;
+        }
+        let rate = zone_query
+            .iter()
+            .filter(|(transform, _)| wall_collision_check(stepped.position, transform.translation))
+            .map(|(_, zone)| zone.rate)
+            .fold(0.0, f32::max);
+
+        // Steps outside any zone still count towards the grace period
+        if tracker.step(&settings, rng.stream(RngStream::Encounters), rate) {
+            let transition = Transition::new(TransitionEffect::Dissolve, GameState::Combat)
+                .with_duration(1.2)
+                .with_easing(Easing::EaseIn);
+            if let Err(err) = transitions.start(&mut commands, &ascii, transition) {
+                warn!("Could not start combat: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_encounters(seed: u64, steps: usize, rate: f32) -> usize {
+        let settings = EncounterSettings::default();
+        let mut rng = Rng::new(seed);
+        let mut tracker = EncounterTracker::default();
+        (0..steps)
+            .filter(|_| tracker.step(&settings, &mut rng, rate))
+            .count()
+    }
+
+    #[test]
+    fn same_seed_same_encounters() {
+        assert_eq!(count_encounters(7, 500, 0.1), count_encounters(7, 500, 0.1));
+    }
+
+    #[test]
+    fn rate_bounds() {
+        assert_eq!(count_encounters(7, 500, 0.0), 0);
+        // Every step past the grace period triggers a battle
+        assert_eq!(count_encounters(7, 500, 1.0), 500 / 5);
+    }
+
+    #[test]
+    fn only_zone_glyphs_have_a_rate() {
+        let settings = EncounterSettings::default();
+        assert_eq!(settings.zone_rate('~'), Some(0.12));
+        assert_eq!(settings.zone_rate('"'), Some(0.06));
+        assert_eq!(settings.zone_rate('.'), None);
+    }
+
+    #[test]
+    fn grace_period_after_battle() {
+        let settings = EncounterSettings {
+            grace_steps: 3,
+            ..default()
+        };
+        let mut rng = Rng::new(1);
+        let mut tracker = EncounterTracker::default();
+        let results: Vec<bool> = (0..8)
+            .map(|_| tracker.step(&settings, &mut rng, 1.0))
+            .collect();
+        assert_eq!(
+            results,
+            vec![false, false, false, true, false, false, false, true]
+        );
+    }
+}
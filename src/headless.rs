@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use bevy::{
+    app::ScheduleRunnerSettings, asset::AssetPlugin, hierarchy::HierarchyPlugin,
+    input::InputPlugin, prelude::*, transform::TransformPlugin,
+};
+
+pub const FRAME_TIME: f32 = 1.0 / 60.0;
+
+// Stands in for DefaultPlugins when there is no window or renderer
+pub fn add_headless_plugins(app: &mut App) -> &mut App {
+    app.insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f32(
+        FRAME_TIME,
+    )))
+    .add_plugins(MinimalPlugins)
+    .add_plugin(AssetPlugin::default())
+    .add_plugin(InputPlugin)
+    .add_plugin(TransformPlugin)
+    .add_plugin(HierarchyPlugin)
+    .add_asset::<Image>()
+    .add_asset::<TextureAtlas>()
+}
@@ -0,0 +1,66 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::camera::VisibleArea;
+
+pub struct HudPlugin;
+
+#[derive(Component)]
+pub struct HudRoot;
+
+// Keeps a HUD element at a point relative to the edges of the visible area, from
+// (-1, -1) at the bottom left to (1, 1) at the top right, plus an offset in HUD units
+#[derive(Component)]
+pub struct HudAnchor {
+    pub anchor: Vec2,
+    pub offset: Vec2,
+}
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, spawn_hud)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                hud_follow_camera.before(TransformSystem::TransformPropagate),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                anchor_hud_elements.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands
+        .spawn(SpatialBundle::default())
+        .insert(Name::new("Hud"))
+        .insert(HudRoot);
+}
+
+fn hud_follow_camera(
+    camera_query: Query<&Transform, (With<Camera>, Without<HudRoot>)>,
+    mut hud_query: Query<&mut Transform, With<HudRoot>>,
+) {
+    if let (Ok(camera_transform), Ok(mut hud_transform)) =
+        (camera_query.get_single(), hud_query.get_single_mut())
+    {
+        hud_transform.translation.x = camera_transform.translation.x;
+        hud_transform.translation.y = camera_transform.translation.y;
+    }
+}
+
+fn anchor_hud_elements(
+    area: Res<VisibleArea>,
+    root_query: Query<&Transform, With<HudRoot>>,
+    mut anchor_query: Query<(&HudAnchor, &mut Transform), Without<HudRoot>>,
+) {
+    // Anchors are in screen space, so undo the UI scale on the root
+    let scale = match root_query.get_single() {
+        Ok(root) => root.scale.truncate(),
+        Err(_) => return,
+    };
+    for (anchor, mut transform) in anchor_query.iter_mut() {
+        let position = anchor.anchor * area.half_size / scale + anchor.offset;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
@@ -0,0 +1,190 @@
+use ascii::AsciiPlugin;
+use bevy::{ecs::schedule::ShouldRun, prelude::*, time::FixedTimestep};
+use camera::VisibleArea;
+use combat::{CombatPlugin, NextEnemy};
+use controls::ControlsPlugin;
+use encounter::EncounterPlugin;
+use hud::HudPlugin;
+use main_menu::MainMenuPlugin;
+use options::OptionsPlugin;
+use pause::PausePlugin;
+use player::{MovementMode, PlayerPlugin};
+use rebinding::RebindingPlugin;
+use replay::{Replay, ReplayPlayer, ReplayPlugin, ReplayRecorder};
+use rng::{seed_from_clock, GameRng};
+use save::{SavePlugin, SAVE_PATH};
+use settings::SettingsPlugin;
+use tilemap::TileMapPlugin;
+use transition::TransitionPlugin;
+use widgets::WidgetPlugin;
+pub mod ascii;
+pub mod camera;
+pub mod cli;
+pub mod collision;
+pub mod combat;
+pub mod controls;
+pub mod cp437;
+pub mod debug;
+pub mod encounter;
+pub mod headless;
+pub mod hud;
+pub mod main_menu;
+pub mod options;
+pub mod pause;
+pub mod player;
+pub mod rebinding;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod settings;
+#[cfg(feature = "test-utils")]
+pub mod test_app;
+pub mod tilemap;
+pub mod transition;
+pub mod widgets;
+
+pub const CLEAR: Color = Color::rgb(0.1, 0.1, 0.1);
+pub const RESOLUTION: f32 = 16.0 / 9.0;
+pub const TILE_SIZE: f32 = 0.1;
+
+// Stage for the simulation that replays have to reproduce: movement, encounters, battles and
+// screen transitions. It runs once per FIXED_TIME_STEP of real time, however fast frames are
+// rendered.
+pub const FIXED_UPDATE: &str = "fixed_update";
+pub const FIXED_TIME_STEP: f32 = 1.0 / 60.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+pub enum GameState {
+    MainMenu,
+    Overworld,
+    Combat,
+    KeyBindings,
+    Options,
+    Paused,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct GameConfig {
+    pub map_path: String,
+    pub movement_mode: MovementMode,
+    // Fixed seed for reproducible runs, otherwise seeded from the clock
+    pub seed: Option<u64>,
+    pub record_path: Option<String>,
+    // Playing a replay takes the seed and movement mode from the recording
+    pub replay_path: Option<String>,
+    // Anything other than the main menu starts a new game straight away
+    pub start_state: GameState,
+    pub enemy: Option<String>,
+    // Quit after this many fixed steps (FIXED_TIME_STEP each), for smoke runs
+    pub max_frames: Option<u64>,
+    // Where the game is saved, or None to never write saves
+    pub save_path: Option<String>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            map_path: "assets/map.txt".to_string(),
+            movement_mode: MovementMode::default(),
+            seed: None,
+            record_path: None,
+            replay_path: None,
+            start_state: GameState::MainMenu,
+            enemy: None,
+            max_frames: None,
+            save_path: Some(SAVE_PATH.to_string()),
+        }
+    }
+}
+
+// Everything that makes up the game itself, independent of windowing and rendering
+#[derive(Default)]
+pub struct GamePlugin {
+    pub config: GameConfig,
+}
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        let replay = self.config.replay_path.as_deref().and_then(|path| {
+            Replay::load(path)
+                .map_err(|err| warn!("Could not load replay {}: {}", path, err))
+                .ok()
+        });
+        let seed = replay
+            .as_ref()
+            .map(|replay| replay.seed)
+            .or(self.config.seed)
+            .unwrap_or_else(seed_from_clock);
+        let movement_mode = replay
+            .as_ref()
+            .map(|replay| replay.movement_mode)
+            .unwrap_or(self.config.movement_mode);
+
+        app.insert_resource(self.config.clone())
+            .insert_resource(movement_mode)
+            .insert_resource(GameRng::new(seed))
+            .init_resource::<VisibleArea>()
+            .add_stage_after(
+                CoreStage::PreUpdate,
+                FIXED_UPDATE,
+                SystemStage::parallel()
+                    .with_run_criteria(FixedTimestep::step(FIXED_TIME_STEP as f64)),
+            );
+
+        if let Some(path) = &self.config.record_path {
+            app.insert_resource(ReplayRecorder::new(path, seed, movement_mode));
+        }
+        if let Some(replay) = replay {
+            app.insert_resource(ReplayPlayer::new(replay));
+        }
+        if let Some(enemy) = &self.config.enemy {
+            app.insert_resource(NextEnemy(enemy.clone()));
+        }
+
+        app.add_state(GameState::MainMenu)
+            .add_plugin(MainMenuPlugin)
+            .add_plugin(SavePlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(EncounterPlugin)
+            .add_plugin(AsciiPlugin)
+            .add_plugin(WidgetPlugin)
+            .add_plugin(HudPlugin)
+            .add_plugin(TransitionPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(OptionsPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(RebindingPlugin)
+            .add_plugin(TileMapPlugin)
+            .add_plugin(CombatPlugin)
+            .add_plugin(ReplayPlugin)
+            .add_startup_system(enter_start_state);
+    }
+}
+
+// State system sets only work in the stage the state is driven from, so systems in the
+// fixed stage check the state with this instead
+pub fn in_overworld(state: Res<State<GameState>>) -> ShouldRun {
+    if state.current() == &GameState::Overworld {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+pub fn in_combat(state: Res<State<GameState>>) -> ShouldRun {
+    if state.current() == &GameState::Combat {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn enter_start_state(config: Res<GameConfig>, mut state: ResMut<State<GameState>>) {
+    if config.start_state != GameState::MainMenu {
+        // The initial transition is still queued during startup, which set would refuse
+        if let Err(err) = state.overwrite_set(config.start_state) {
+            warn!("Could not start in {:?}: {:?}", config.start_state, err);
+        }
+    }
+}
@@ -1,65 +1,58 @@
-use ascii::AsciiPlugin;
-use bevy::{prelude::*, render::camera::ScalingMode, window::PresentMode};
-use combat::CombatPlugin;
-use debug::DebugPlugin;
-use fadeout::FadeoutPlugin;
-use player::PlayerPlugin;
-use tilemap::TileMapPlugin;
-mod ascii;
-mod combat;
-mod debug;
-mod fadeout;
-mod player;
-mod tilemap;
-
-pub const CLEAR: Color = Color::rgb(0.1, 0.1, 0.1);
-pub const RESOLUTION: f32 = 16.0 / 9.0;
-pub const TILE_SIZE: f32 = 0.1;
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
-pub enum GameState {
-    Overworld,
-    Combat,
-}
+use bevy::prelude::*;
+use tutorial::{
+    camera::CameraPlugin,
+    cli::{CliOptions, USAGE},
+    debug::DebugPlugin,
+    headless::add_headless_plugins,
+    settings::{Settings, WindowSizeOverride},
+    GamePlugin, CLEAR, RESOLUTION,
+};
 
 fn main() {
-    let height = 900.0;
-    App::new()
-        .add_state(GameState::Overworld)
-        .insert_resource(ClearColor(CLEAR))
-        .add_plugins(
-            DefaultPlugins
-                .set(WindowPlugin {
-                    window: WindowDescriptor {
-                        width: height * RESOLUTION,
-                        height: height,
-                        title: "Bevy Tutorial".to_string(),
-                        resizable: false,
-                        present_mode: PresentMode::Fifo,
-                        ..default()
-                    },
-                    ..default()
-                })
-                .set(ImagePlugin::default_nearest()),
-        )
-        .add_startup_system(spawn_camera)
-        .add_plugin(DebugPlugin)
-        .add_plugin(PlayerPlugin)
-        .add_plugin(AsciiPlugin)
-        .add_plugin(FadeoutPlugin)
-        .add_plugin(TileMapPlugin)
-        .add_plugin(CombatPlugin)
-        .run();
-}
-
-fn spawn_camera(mut commands: Commands) {
-    let mut camera = Camera2dBundle::default();
+    let options = match CliOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
 
-    camera.projection.top = 1.0;
-    camera.projection.bottom = -1.0;
-    camera.projection.right = 1.0 * RESOLUTION;
-    camera.projection.left = -1.0 * RESOLUTION;
-
-    camera.projection.scaling_mode = ScalingMode::None;
-    commands.spawn(camera);
+    let mut app = App::new();
+    if options.headless {
+        add_headless_plugins(&mut app);
+    } else {
+        let settings = Settings::load_or_default();
+        // Sizes given on the command line win over the settings file, without being saved to it
+        let size_override = WindowSizeOverride(match (options.width, options.height) {
+            (width, Some(height)) => Some(Vec2::new(width.unwrap_or(height * RESOLUTION), height)),
+            (Some(width), None) => Some(Vec2::new(width, width / RESOLUTION)),
+            (None, None) => None,
+        });
+        let size = size_override.size(&settings);
+        app.insert_resource(ClearColor(CLEAR))
+            .add_plugins(
+                DefaultPlugins
+                    .set(WindowPlugin {
+                        window: WindowDescriptor {
+                            width: size.x,
+                            height: size.y,
+                            ..settings.window_descriptor()
+                        },
+                        ..default()
+                    })
+                    .set(ImagePlugin::default_nearest()),
+            )
+            .add_plugin(CameraPlugin)
+            .add_plugin(DebugPlugin)
+            .insert_resource(settings)
+            .insert_resource(size_override);
+    }
+    app.add_plugin(GamePlugin {
+        config: options.config,
+    })
+    .run();
 }
@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    ascii::{
+        measure_text, spawn_ascii_text_aligned, AsciiSheet, HorizontalAlign, TextLayout,
+        VerticalAlign,
+    },
+    controls::{Action, InputContext, InputContexts},
+    hud::HudRoot,
+    save::{CurrentSave, SaveGame},
+    widgets::{spawn_ascii_list, AsciiList},
+    GameConfig, GameState, TILE_SIZE,
+};
+
+const TITLE: &str = r" ___ ___ _   ___   __
+| _ ) __\ \ / /\ \ / /
+| _ \ _| \ V /  \ V /
+|___/___| \_/    |_|
+ -- a tutorial rpg --";
+
+pub struct MainMenuPlugin;
+
+#[derive(Component)]
+struct MainMenuScreen;
+
+#[derive(Component)]
+struct MainMenuList(Vec<MenuItem>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuItem {
+    NewGame,
+    Continue,
+    Options,
+    Quit,
+}
+
+impl MenuItem {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuItem::NewGame => "New Game",
+            MenuItem::Continue => "Continue",
+            MenuItem::Options => "Options",
+            MenuItem::Quit => "Quit",
+        }
+    }
+}
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(spawn_main_menu))
+            .add_system_set(SystemSet::on_update(GameState::MainMenu).with_system(main_menu_input))
+            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(despawn_main_menu));
+    }
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    config: Res<GameConfig>,
+    mut contexts: ResMut<InputContexts>,
+    hud_query: Query<Entity, With<HudRoot>>,
+) {
+    contexts.push(InputContext::Menu);
+
+    // Continue is only offered once there is something to continue
+    let has_save = config
+        .save_path
+        .as_ref()
+        .is_some_and(|path| Path::new(path).exists());
+    let items: Vec<MenuItem> = [
+        MenuItem::NewGame,
+        MenuItem::Continue,
+        MenuItem::Options,
+        MenuItem::Quit,
+    ]
+    .into_iter()
+    .filter(|item| *item != MenuItem::Continue || has_save)
+    .collect();
+    let labels: Vec<&str> = items.iter().map(|item| item.label()).collect();
+
+    // Centred as a block, since centring each line would shear the art
+    let layout = TextLayout::new(HorizontalAlign::Left, VerticalAlign::Middle);
+    let title_width = measure_text(TITLE, layout).x * TILE_SIZE;
+    let title = spawn_ascii_text_aligned(
+        &mut commands,
+        &ascii,
+        TITLE,
+        Vec3::new(-title_width / 2.0, 5.0 * TILE_SIZE, 0.0),
+        layout,
+    );
+    let list = spawn_ascii_list(
+        &mut commands,
+        &ascii,
+        Vec3::new(-5.0 * TILE_SIZE, -TILE_SIZE, 0.0),
+        &labels,
+    );
+    commands.entity(list).insert(MainMenuList(items));
+
+    let screen = commands
+        .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+            0.0, 0.0, 600.0,
+        )))
+        .insert(Name::new("MainMenu"))
+        .insert(MainMenuScreen)
+        .push_children(&[title, list])
+        .id();
+    commands.entity(hud_query.single()).add_child(screen);
+}
+
+fn main_menu_input(
+    mut actions: ResMut<Input<Action>>,
+    contexts: Res<InputContexts>,
+    config: Res<GameConfig>,
+    mut current: ResMut<CurrentSave>,
+    mut state: ResMut<State<GameState>>,
+    mut exit_event: EventWriter<AppExit>,
+    mut list_query: Query<(&mut AsciiList, &MainMenuList)>,
+) {
+    if !contexts.is_active(InputContext::Menu) {
+        return;
+    }
+    let (mut list, items) = match list_query.get_single_mut() {
+        Ok(list) => list,
+        Err(_) => return,
+    };
+
+    if actions.just_pressed(Action::MoveUp) {
+        list.select_previous();
+    } else if actions.just_pressed(Action::MoveDown) {
+        list.select_next();
+    } else if actions.clear_just_pressed(Action::Confirm) {
+        match items.0[list.selected] {
+            MenuItem::NewGame => {
+                current.0 = None;
+                start_game(&mut state);
+            }
+            MenuItem::Continue => {
+                let path = config.save_path.as_deref().unwrap_or_default();
+                match SaveGame::load(path) {
+                    Ok(save) => {
+                        current.0 = Some(save);
+                        start_game(&mut state);
+                    }
+                    Err(err) => warn!("Could not load save {}: {}", path, err),
+                }
+            }
+            MenuItem::Options => {
+                if let Err(err) = state.push(GameState::Options) {
+                    warn!("Could not open options: {:?}", err);
+                }
+            }
+            MenuItem::Quit => exit_event.send(AppExit),
+        }
+    }
+}
+
+// The player and map are spawned on leaving the menu, from the save in CurrentSave if any
+fn start_game(state: &mut State<GameState>) {
+    if let Err(err) = state.set(GameState::Overworld) {
+        warn!("Could not start the game: {:?}", err);
+    }
+}
+
+fn despawn_main_menu(
+    mut commands: Commands,
+    mut contexts: ResMut<InputContexts>,
+    screen_query: Query<Entity, With<MainMenuScreen>>,
+) {
+    contexts.pop(InputContext::Menu);
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+
+use crate::{
+    ascii::{spawn_ascii_text_aligned, AsciiSheet, HorizontalAlign, TextLayout, VerticalAlign},
+    controls::{Action, InputContext, InputContexts},
+    hud::HudRoot,
+    settings::{Settings, WindowSizeOverride, SETTINGS_PATH},
+    widgets::{spawn_ascii_list, spawn_ascii_panel, AsciiList},
+    GameState, TILE_SIZE,
+};
+
+const PANEL_WIDTH: usize = 30;
+const PANEL_HEIGHT: usize = 12;
+
+pub struct OptionsPlugin;
+
+#[derive(Component)]
+struct OptionsScreen;
+
+#[derive(Component)]
+struct OptionsList;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OptionItem {
+    Resolution,
+    Fullscreen,
+    VSync,
+    Resizable,
+    UiScale,
+    Scaling,
+    Controls,
+    Back,
+}
+
+impl OptionItem {
+    const ALL: [OptionItem; 8] = [
+        OptionItem::Resolution,
+        OptionItem::Fullscreen,
+        OptionItem::VSync,
+        OptionItem::Resizable,
+        OptionItem::UiScale,
+        OptionItem::Scaling,
+        OptionItem::Controls,
+        OptionItem::Back,
+    ];
+
+    fn describe(&self, settings: &Settings, size_override: &WindowSizeOverride) -> String {
+        let on_off = |value: bool| if value { "On" } else { "Off" };
+        match self {
+            OptionItem::Resolution => {
+                let size = size_override.size(settings);
+                format!("{:<12}{}x{}", "Resolution", size.x, size.y)
+            }
+            OptionItem::Fullscreen => {
+                format!("{:<12}{}", "Fullscreen", on_off(settings.fullscreen))
+            }
+            OptionItem::VSync => format!("{:<12}{}", "VSync", on_off(settings.vsync)),
+            OptionItem::Resizable => format!("{:<12}{}", "Resizable", on_off(settings.resizable)),
+            OptionItem::UiScale => format!("{:<12}{}x", "UI Scale", settings.ui_scale),
+            OptionItem::Scaling => format!("{:<12}{}", "Scaling", settings.scaling.label()),
+            OptionItem::Controls => "Controls".to_string(),
+            OptionItem::Back => "Back".to_string(),
+        }
+    }
+
+    // Returns false for items that have no value to change
+    fn change(
+        &self,
+        settings: &mut Settings,
+        size_override: &mut WindowSizeOverride,
+        forward: bool,
+    ) -> bool {
+        match self {
+            OptionItem::Resolution => {
+                settings.cycle_resolution(forward);
+                size_override.0 = None;
+            }
+            OptionItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            OptionItem::VSync => settings.vsync = !settings.vsync,
+            OptionItem::Resizable => settings.resizable = !settings.resizable,
+            OptionItem::UiScale => settings.cycle_ui_scale(forward),
+            OptionItem::Scaling => settings.cycle_scaling(forward),
+            OptionItem::Controls | OptionItem::Back => return false,
+        }
+        true
+    }
+}
+
+impl Plugin for OptionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Overworld).with_system(open_options_screen),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::Options).with_system(spawn_options_screen))
+        .add_system_set(SystemSet::on_update(GameState::Options).with_system(options_input))
+        .add_system_set(SystemSet::on_exit(GameState::Options).with_system(despawn_options_screen));
+    }
+}
+
+fn open_options_screen(
+    mut actions: ResMut<Input<Action>>,
+    contexts: Res<InputContexts>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if contexts.is_active(InputContext::Overworld) && actions.clear_just_pressed(Action::Menu) {
+        if let Err(err) = state.push(GameState::Options) {
+            warn!("Could not open options: {:?}", err);
+        }
+    }
+}
+
+fn spawn_options_screen(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    settings: Res<Settings>,
+    size_override: Res<WindowSizeOverride>,
+    mut contexts: ResMut<InputContexts>,
+    hud_query: Query<Entity, With<HudRoot>>,
+) {
+    contexts.push(InputContext::Menu);
+
+    let panel = spawn_ascii_panel(
+        &mut commands,
+        &ascii,
+        Vec3::new(0.0, 0.0, 940.0),
+        PANEL_WIDTH,
+        PANEL_HEIGHT,
+        Some("Options"),
+    );
+    let list = spawn_options_list(&mut commands, &ascii, &settings, &size_override, 0);
+    let hint = spawn_ascii_text_aligned(
+        &mut commands,
+        &ascii,
+        "Left/Right: change   Cancel: back",
+        Vec3::new(0.0, -((PANEL_HEIGHT / 2) as f32 - 1.0) * TILE_SIZE, 2.0),
+        TextLayout::new(HorizontalAlign::Center, VerticalAlign::Middle),
+    );
+
+    commands
+        .entity(panel)
+        .insert(OptionsScreen)
+        .push_children(&[list, hint]);
+    commands.entity(hud_query.single()).add_child(panel);
+}
+
+fn spawn_options_list(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    settings: &Settings,
+    size_override: &WindowSizeOverride,
+    selected: usize,
+) -> Entity {
+    let items: Vec<String> = OptionItem::ALL
+        .iter()
+        .map(|item| item.describe(settings, size_override))
+        .collect();
+    let item_refs: Vec<&str> = items.iter().map(|item| item.as_str()).collect();
+
+    let top_left = Vec3::new(
+        -((PANEL_WIDTH / 2) as f32 - 2.5) * TILE_SIZE,
+        ((PANEL_HEIGHT / 2) as f32 - 2.0) * TILE_SIZE,
+        2.0,
+    );
+    let list = spawn_ascii_list(commands, ascii, top_left, &item_refs);
+    commands
+        .entity(list)
+        .insert(AsciiList { items, selected })
+        .insert(OptionsList);
+    list
+}
+
+#[allow(clippy::too_many_arguments)]
+fn options_input(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    mut actions: ResMut<Input<Action>>,
+    contexts: Res<InputContexts>,
+    mut settings: ResMut<Settings>,
+    mut size_override: ResMut<WindowSizeOverride>,
+    mut state: ResMut<State<GameState>>,
+    mut list_query: Query<(Entity, &mut AsciiList), With<OptionsList>>,
+    screen_query: Query<Entity, With<OptionsScreen>>,
+) {
+    if !contexts.is_active(InputContext::Menu) {
+        return;
+    }
+    let (list_entity, mut list) = match list_query.get_single_mut() {
+        Ok(list) => list,
+        Err(_) => return,
+    };
+    let item = OptionItem::ALL[list.selected];
+
+    let changed = if actions.just_pressed(Action::MoveUp) {
+        list.select_previous();
+        false
+    } else if actions.just_pressed(Action::MoveDown) {
+        list.select_next();
+        false
+    } else if actions.just_pressed(Action::MoveLeft) {
+        item.change(&mut settings, &mut size_override, false)
+    } else if actions.just_pressed(Action::MoveRight) {
+        item.change(&mut settings, &mut size_override, true)
+    } else if actions.clear_just_pressed(Action::Confirm) {
+        match item {
+            OptionItem::Controls => {
+                // Cleared above so the controls screen doesn't start rebinding on this press
+                if let Err(err) = state.push(GameState::KeyBindings) {
+                    warn!("Could not open key bindings: {:?}", err);
+                }
+                false
+            }
+            OptionItem::Back => {
+                close_options(&settings, &mut state);
+                false
+            }
+            _ => item.change(&mut settings, &mut size_override, true),
+        }
+    } else if actions.clear_just_pressed(Action::Cancel) {
+        // Escape is also Pause, which the resumed state would see in this same frame
+        actions.clear_just_pressed(Action::Pause);
+        close_options(&settings, &mut state);
+        false
+    } else {
+        false
+    };
+
+    if changed {
+        commands.entity(list_entity).despawn_recursive();
+        let list = spawn_options_list(
+            &mut commands,
+            &ascii,
+            &settings,
+            &size_override,
+            list.selected,
+        );
+        commands.entity(screen_query.single()).add_child(list);
+    }
+}
+
+fn close_options(settings: &Settings, state: &mut State<GameState>) {
+    if let Err(err) = settings.save(SETTINGS_PATH) {
+        warn!("Could not save settings: {}", err);
+    }
+    if let Err(err) = state.pop() {
+        warn!("Could not close options: {:?}", err);
+    }
+}
+
+fn despawn_options_screen(
+    mut commands: Commands,
+    mut contexts: ResMut<InputContexts>,
+    screen_query: Query<Entity, With<OptionsScreen>>,
+) {
+    contexts.pop(InputContext::Menu);
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
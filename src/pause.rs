@@ -0,0 +1,142 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    ascii::AsciiSheet,
+    controls::{Action, InputContext, InputContexts},
+    hud::HudRoot,
+    widgets::{spawn_ascii_list, spawn_ascii_panel, AsciiList},
+    GameState, TILE_SIZE,
+};
+
+const PANEL_WIDTH: usize = 16;
+const PANEL_HEIGHT: usize = 7;
+const ITEMS: [&str; 3] = ["Resume", "Options", "Quit"];
+
+pub struct PausePlugin;
+
+#[derive(Component)]
+struct PauseScreen;
+
+#[derive(Component)]
+struct PauseList;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        // Paused is pushed on top of the game, so the paused state keeps its entities and
+        // its on_exit systems don't run
+        app.add_system_set(SystemSet::on_update(GameState::Overworld).with_system(open_pause))
+            .add_system_set(SystemSet::on_update(GameState::Combat).with_system(open_pause))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_screen))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(pause_input))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(despawn_pause_screen),
+            );
+    }
+}
+
+fn open_pause(
+    mut actions: ResMut<Input<Action>>,
+    contexts: Res<InputContexts>,
+    mut state: ResMut<State<GameState>>,
+) {
+    // Screen transitions can be paused too, other menus can't
+    if contexts.is_active(InputContext::Menu) || !actions.clear_just_pressed(Action::Pause) {
+        return;
+    }
+    // Escape is also Cancel, which would close the pause screen again straight away
+    actions.clear_just_pressed(Action::Cancel);
+    if let Err(err) = state.push(GameState::Paused) {
+        warn!("Could not pause: {:?}", err);
+    }
+}
+
+fn spawn_pause_screen(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    mut time: ResMut<Time>,
+    mut contexts: ResMut<InputContexts>,
+    hud_query: Query<Entity, With<HudRoot>>,
+) {
+    // Stops everything driven by Time, like combat phase timers and transitions
+    time.pause();
+    contexts.push(InputContext::Menu);
+
+    let panel = spawn_ascii_panel(
+        &mut commands,
+        &ascii,
+        Vec3::new(0.0, 0.0, 930.0),
+        PANEL_WIDTH,
+        PANEL_HEIGHT,
+        Some("Paused"),
+    );
+    let list = spawn_ascii_list(
+        &mut commands,
+        &ascii,
+        Vec3::new(
+            -((PANEL_WIDTH / 2) as f32 - 2.5) * TILE_SIZE,
+            ((PANEL_HEIGHT / 2) as f32 - 2.0) * TILE_SIZE,
+            2.0,
+        ),
+        &ITEMS,
+    );
+    commands.entity(list).insert(PauseList);
+
+    commands.entity(panel).insert(PauseScreen).add_child(list);
+    commands.entity(hud_query.single()).add_child(panel);
+}
+
+fn pause_input(
+    mut actions: ResMut<Input<Action>>,
+    contexts: Res<InputContexts>,
+    mut state: ResMut<State<GameState>>,
+    mut exit_event: EventWriter<AppExit>,
+    mut list_query: Query<&mut AsciiList, With<PauseList>>,
+) {
+    if !contexts.is_active(InputContext::Menu) {
+        return;
+    }
+    let mut list = match list_query.get_single_mut() {
+        Ok(list) => list,
+        Err(_) => return,
+    };
+
+    // Escape is bound to both, and the resumed state mustn't see either press
+    let pause = actions.clear_just_pressed(Action::Pause);
+    let cancel = actions.clear_just_pressed(Action::Cancel);
+    if pause || cancel {
+        resume(&mut state);
+    } else if actions.just_pressed(Action::MoveUp) {
+        list.select_previous();
+    } else if actions.just_pressed(Action::MoveDown) {
+        list.select_next();
+    } else if actions.clear_just_pressed(Action::Confirm) {
+        match ITEMS[list.selected] {
+            "Resume" => resume(&mut state),
+            "Options" => {
+                if let Err(err) = state.push(GameState::Options) {
+                    warn!("Could not open options: {:?}", err);
+                }
+            }
+            _ => exit_event.send(AppExit),
+        }
+    }
+}
+
+fn resume(state: &mut State<GameState>) {
+    if let Err(err) = state.pop() {
+        warn!("Could not resume: {:?}", err);
+    }
+}
+
+fn despawn_pause_screen(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    mut contexts: ResMut<InputContexts>,
+    screen_query: Query<Entity, With<PauseScreen>>,
+) {
+    time.unpause();
+    contexts.pop(InputContext::Menu);
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,53 +1,63 @@
-use bevy::{prelude::*, sprite::collide_aabb::collide};
+use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     ascii::{spawn_ascii_sprite, AsciiSheet},
+    collision::{move_and_slide, Aabb},
     combat::CombatStats,
-    fadeout::create_fadeout,
-    tilemap::{EncounterSpawner, TileCollider},
-    GameState, TILE_SIZE,
+    controls::{Action, InputContext, InputContexts, StepActions},
+    encounter::EncounterTracker,
+    in_overworld,
+    save::CurrentSave,
+    tilemap::TileCollider,
+    GameState, FIXED_TIME_STEP, FIXED_UPDATE, TILE_SIZE,
 };
 
 pub struct PlayerPlugin;
 
-#[derive(Default, Component, Reflect)]
-#[reflect(Component)]
-pub struct EncounterTrackrer {
-    timer: Timer,
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovementMode {
+    #[default]
+    Continuous,
+    Grid,
+}
+
+// Sent for every tile's worth of distance the player covers
+pub struct PlayerStepped {
+    pub position: Vec3,
+}
+
+#[derive(Component)]
+struct GridStep {
+    from: Vec3,
+    to: Vec3,
+    progress: f32,
 }
 
 #[derive(Component, Inspectable)]
 pub struct Player {
     speed: f32,
     just_moved: bool,
+    step_distance: f32,
+    pub experience: usize,
 }
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::Overworld).with_system(show_player))
+        app.init_resource::<MovementMode>()
+            .add_event::<PlayerStepped>()
+            .add_system_set(SystemSet::on_enter(GameState::Overworld).with_system(show_player))
             .add_system_set(SystemSet::on_exit(GameState::Overworld).with_system(hide_player))
-            .add_system_set(
-                SystemSet::on_update(GameState::Overworld)
-                    .with_system(player_encounter_checking.after("movement"))
-                    .with_system(camera_follow.after("movement"))
-                    .with_system(player_movement.label("movement")),
+            .add_system_set(SystemSet::on_update(GameState::Overworld).with_system(camera_follow))
+            .add_system_set_to_stage(
+                FIXED_UPDATE,
+                SystemSet::new()
+                    .with_run_criteria(in_overworld)
+                    .with_system(player_movement.label("movement"))
+                    .with_system(grid_movement.label("movement")),
             )
-            .add_system_set(SystemSet::on_update(GameState::Combat).with_system(test_exit_combad))
-            .add_startup_system(spawn_player);
-    }
-}
-
-fn test_exit_combad(
-    mut commands: Commands,
-    mut keyboard: ResMut<Input<KeyCode>>,
-    mut state: ResMut<State<GameState>>,
-    ascii: Res<AsciiSheet>,
-) {
-    if keyboard.just_pressed(KeyCode::Space) {
-        println!("Changeing to Overworld");
-        create_fadeout(&mut commands, GameState::Overworld, &ascii);
-        keyboard.clear();
+            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(spawn_player));
     }
 }
 
@@ -88,122 +98,183 @@ fn show_player(
 fn player_movement(
     mut player_query: Query<(&mut Player, &mut Transform)>,
     wall_query: Query<&Transform, (With<TileCollider>, Without<Player>)>,
-    keyboard: Res<Input<KeyCode>>,
-    time: Res<Time>,
+    actions: Res<StepActions>,
+    contexts: Res<InputContexts>,
+    mode: Res<MovementMode>,
+    mut stepped_event: EventWriter<PlayerStepped>,
 ) {
+    if *mode != MovementMode::Continuous {
+        return;
+    }
     let (mut player, mut transform) = player_query.single_mut();
     player.just_moved = false;
+    let start = transform.translation;
 
-    let mut y_delta = 0.0;
-    if keyboard.pressed(KeyCode::W) {
-        y_delta += player.speed * TILE_SIZE * time.delta_seconds();
-    }
-    if keyboard.pressed(KeyCode::S) {
-        y_delta -= player.speed * TILE_SIZE * time.delta_seconds();
+    if !contexts.is_active(InputContext::Overworld) {
+        return;
     }
 
-    let mut x_delta = 0.0;
-    if keyboard.pressed(KeyCode::A) {
-        x_delta -= player.speed * TILE_SIZE * time.delta_seconds();
+    let mut direction = Vec2::ZERO;
+    if actions.pressed(Action::MoveUp) {
+        direction.y += 1.0;
     }
-    if keyboard.pressed(KeyCode::D) {
-        x_delta += player.speed * TILE_SIZE * time.delta_seconds();
+    if actions.pressed(Action::MoveDown) {
+        direction.y -= 1.0;
     }
-
-    let target = transform.translation + Vec3::new(x_delta, 0.0, 0.0);
-    if !wall_query
-        .iter()
-        .any(|&transform| wall_collision_check(target, transform.translation))
-    {
-        transform.translation = target;
-        if x_delta != 0.0 {
-            player.just_moved = true;
-        }
+    if actions.pressed(Action::MoveLeft) {
+        direction.x -= 1.0;
     }
+    if actions.pressed(Action::MoveRight) {
+        direction.x += 1.0;
+    }
+    let delta = direction * player.speed * TILE_SIZE * FIXED_TIME_STEP;
 
-    let target = transform.translation + Vec3::new(0.0, y_delta, 0.0);
-    if !wall_query
+    let player_box = player_aabb(transform.translation);
+    let reach = player_box.swept(delta);
+    let walls: Vec<Aabb> = wall_query
         .iter()
-        .any(|&transform| wall_collision_check(target, transform.translation))
-    {
-        transform.translation = target;
-        if y_delta != 0.0 {
-            player.just_moved = true;
-        }
+        .map(|transform| tile_aabb(transform.translation))
+        .filter(|wall| reach.overlaps(wall))
+        .collect();
+
+    let target = move_and_slide(&player_box, delta, &walls);
+    transform.translation = target.extend(transform.translation.z);
+    player.just_moved = transform.translation != start;
+
+    let moved = (transform.translation - start).abs();
+    player.step_distance += moved.x + moved.y;
+    while player.step_distance >= TILE_SIZE {
+        player.step_distance -= TILE_SIZE;
+        stepped_event.send(PlayerStepped {
+            position: transform.translation,
+        });
     }
 }
 
-fn player_encounter_checking(
+fn grid_movement(
     mut commands: Commands,
-    mut player_query: Query<(&mut Player, &mut EncounterTrackrer, &Transform)>,
-    encounter_query: Query<&mut Transform, (With<EncounterSpawner>, Without<Player>)>,
-    mut state: ResMut<State<GameState>>,
-    ascii: Res<AsciiSheet>,
-    time: Res<Time>,
+    mut player_query: Query<(Entity, &mut Player, &mut Transform, Option<&mut GridStep>)>,
+    wall_query: Query<&Transform, (With<TileCollider>, Without<Player>)>,
+    actions: Res<StepActions>,
+    contexts: Res<InputContexts>,
+    mode: Res<MovementMode>,
+    mut stepped_event: EventWriter<PlayerStepped>,
 ) {
-    let (player, mut encounter_tracker, player_transform) = player_query.single_mut();
-    let player_translation = player_transform.translation;
-
-    if player.just_moved
-        && encounter_query
-            .iter()
-            .any(|&transform| wall_collision_check(player_translation, transform.translation))
-    {
-        encounter_tracker.timer.tick(time.delta());
+    if *mode != MovementMode::Grid {
+        return;
+    }
+    let (entity, mut player, mut transform, step) = player_query.single_mut();
+    player.just_moved = false;
 
-        if encounter_tracker.timer.just_finished() {
-            create_fadeout(&mut commands, GameState::Combat, &ascii);
+    // A step in progress always finishes, even if input is taken away mid-way
+    if let Some(mut step) = step {
+        step.progress = (step.progress + player.speed * FIXED_TIME_STEP).min(1.0);
+        transform.translation = step.from.lerp(step.to, step.progress);
+        player.just_moved = true;
+        if step.progress >= 1.0 {
+            commands.entity(entity).remove::<GridStep>();
+            stepped_event.send(PlayerStepped { position: step.to });
         }
+        return;
+    }
+
+    if !contexts.is_active(InputContext::Overworld) {
+        return;
+    }
+
+    let direction = if actions.pressed(Action::MoveUp) {
+        Vec3::Y
+    } else if actions.pressed(Action::MoveDown) {
+        Vec3::NEG_Y
+    } else if actions.pressed(Action::MoveLeft) {
+        Vec3::NEG_X
+    } else if actions.pressed(Action::MoveRight) {
+        Vec3::X
+    } else {
+        return;
+    };
+
+    let from = snap_to_grid(transform.translation);
+    let to = from + direction * TILE_SIZE;
+    transform.translation = from;
+    if !wall_query
+        .iter()
+        .any(|&transform| wall_collision_check(to, transform.translation))
+    {
+        commands.entity(entity).insert(GridStep {
+            from,
+            to,
+            progress: 0.0,
+        });
     }
 }
 
+fn snap_to_grid(translation: Vec3) -> Vec3 {
+    Vec3::new(
+        (translation.x / TILE_SIZE).round() * TILE_SIZE,
+        (translation.y / TILE_SIZE).round() * TILE_SIZE,
+        translation.z,
+    )
+}
+
 fn camera_follow(
     player_query: Query<&Transform, With<Player>>,
     mut camera_query: Query<&mut Transform, (Without<Player>, With<Camera>)>,
 ) {
     let player_transform = player_query.single();
-    let mut camera_transform = camera_query.single_mut();
+    // There is no camera when running headless
+    if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+        camera_transform.translation.x = player_transform.translation.x;
+        camera_transform.translation.y = player_transform.translation.y;
+    }
+}
 
-    camera_transform.translation.x = player_transform.translation.x;
-    camera_transform.translation.y = player_transform.translation.y;
+fn player_aabb(translation: Vec3) -> Aabb {
+    Aabb::new(translation.truncate(), Vec2::splat(TILE_SIZE * 0.9))
 }
 
-fn wall_collision_check(target_player_pos: Vec3, wall_translation: Vec3) -> bool {
-    let collision = collide(
-        target_player_pos,
-        Vec2::splat(TILE_SIZE * 0.9),
-        wall_translation,
-        Vec2::splat(TILE_SIZE),
-    );
-    collision.is_some()
+fn tile_aabb(translation: Vec3) -> Aabb {
+    Aabb::new(translation.truncate(), Vec2::splat(TILE_SIZE))
+}
+
+pub fn wall_collision_check(target_player_pos: Vec3, wall_translation: Vec3) -> bool {
+    player_aabb(target_player_pos).overlaps(&tile_aabb(wall_translation))
 }
 
-fn spawn_player(mut commands: Commands, ascii: Res<AsciiSheet>) {
+// Runs when a game is started from the main menu. Everything starts hidden and is shown on
+// entering the overworld, so starting straight into a battle doesn't draw the map over it.
+fn spawn_player(mut commands: Commands, ascii: Res<AsciiSheet>, current: Res<CurrentSave>) {
+    let (position, experience, health) = match &current.0 {
+        Some(save) => (save.position, save.experience, save.health),
+        None => (Vec3::new(2.0 * TILE_SIZE, -2.0 * TILE_SIZE, 900.0), 0, 10),
+    };
     let player = spawn_ascii_sprite(
         &mut commands,
         &ascii,
         1,
         Color::rgb(0.3, 0.3, 0.9),
-        Vec3::new(2.0 * TILE_SIZE, -2.0 * TILE_SIZE, 900.0),
+        position,
         Vec3::splat(1.0),
     );
 
     commands
         .entity(player)
         .insert(Name::new("Player"))
+        .insert(Visibility::INVISIBLE)
         .insert(Player {
             speed: 3.0,
             just_moved: false,
+            step_distance: 0.0,
+            experience,
         })
         .insert(CombatStats {
-            health: 10,
+            health,
             attack: 2,
             defense: 1,
+            speed: 4,
             max_halth: 10,
         })
-        .insert(EncounterTrackrer {
-            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
-        });
+        .insert(EncounterTracker::default());
 
     let background = spawn_ascii_sprite(
         &mut commands,
@@ -217,7 +288,23 @@ fn spawn_player(mut commands: Commands, ascii: Res<AsciiSheet>) {
         },
         Vec3::splat(1.0),
     );
-    commands.entity(background).insert(Name::new("Background"));
+    commands
+        .entity(background)
+        .insert(Name::new("Background"))
+        .insert(Visibility::INVISIBLE);
 
     commands.entity(player).push_children(&[background]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapping_rounds_to_nearest_tile() {
+        let snapped = snap_to_grid(Vec3::new(0.26, -0.14, 900.0));
+        assert!((snapped.x - 0.3).abs() < 1e-5);
+        assert!((snapped.y + 0.1).abs() < 1e-5);
+        assert_eq!(snapped.z, 900.0);
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+
+use crate::{
+    ascii::{spawn_ascii_text_aligned, AsciiSheet, HorizontalAlign, TextLayout, VerticalAlign},
+    controls::{Action, InputBindings, InputContext, InputContexts, INPUT_CONFIG_PATH},
+    hud::HudRoot,
+    widgets::{spawn_ascii_list, spawn_ascii_panel, AsciiList},
+    GameState, TILE_SIZE,
+};
+
+const PANEL_WIDTH: usize = 34;
+const PANEL_HEIGHT: usize = 13;
+
+pub struct RebindingPlugin;
+
+#[derive(Component)]
+struct RebindingScreen;
+
+#[derive(Component)]
+struct RebindingList;
+
+#[derive(Component)]
+struct RebindingHint;
+
+#[derive(Resource, Default)]
+struct Rebinding {
+    waiting: Option<Action>,
+}
+
+impl Plugin for RebindingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::KeyBindings).with_system(spawn_rebinding_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::KeyBindings).with_system(rebinding_input),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::KeyBindings).with_system(despawn_rebinding_screen),
+            );
+    }
+}
+
+fn spawn_rebinding_screen(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    bindings: Res<InputBindings>,
+    mut contexts: ResMut<InputContexts>,
+    mut rebinding: ResMut<Rebinding>,
+    hud_query: Query<Entity, With<HudRoot>>,
+) {
+    contexts.push(InputContext::Menu);
+    rebinding.waiting = None;
+
+    let panel = spawn_ascii_panel(
+        &mut commands,
+        &ascii,
+        Vec3::new(0.0, 0.0, 950.0),
+        PANEL_WIDTH,
+        PANEL_HEIGHT,
+        Some("Controls"),
+    );
+    let list = spawn_bindings_list(&mut commands, &ascii, &bindings, 0);
+    let hint = spawn_hint(&mut commands, &ascii, None);
+
+    commands
+        .entity(panel)
+        .insert(RebindingScreen)
+        .push_children(&[list, hint]);
+    commands.entity(hud_query.single()).add_child(panel);
+}
+
+fn spawn_bindings_list(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    bindings: &InputBindings,
+    selected: usize,
+) -> Entity {
+    let items: Vec<String> = Action::ALL
+        .iter()
+        .map(|action| format!("{:<12}{}", action.label(), bindings.describe(*action)))
+        .collect();
+    let item_refs: Vec<&str> = items.iter().map(|item| item.as_str()).collect();
+
+    let top_left = Vec3::new(
+        -((PANEL_WIDTH / 2) as f32 - 2.5) * TILE_SIZE,
+        ((PANEL_HEIGHT / 2) as f32 - 2.0) * TILE_SIZE,
+        2.0,
+    );
+    let list = spawn_ascii_list(commands, ascii, top_left, &item_refs);
+    commands
+        .entity(list)
+        .insert(AsciiList { items, selected })
+        .insert(RebindingList);
+    list
+}
+
+fn spawn_hint(commands: &mut Commands, ascii: &AsciiSheet, waiting: Option<Action>) -> Entity {
+    let text = match waiting {
+        Some(action) => format!("Press a key for {}", action.label()),
+        None => "Confirm: rebind   Cancel: back".to_string(),
+    };
+    let hint = spawn_ascii_text_aligned(
+        commands,
+        ascii,
+        &text,
+        Vec3::new(0.0, -((PANEL_HEIGHT / 2) as f32 - 1.0) * TILE_SIZE, 2.0),
+        TextLayout::new(HorizontalAlign::Center, VerticalAlign::Middle),
+    );
+    commands.entity(hint).insert(RebindingHint);
+    hint
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rebinding_input(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<Action>>,
+    contexts: Res<InputContexts>,
+    mut bindings: ResMut<InputBindings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut state: ResMut<State<GameState>>,
+    mut list_query: Query<(Entity, &mut AsciiList), With<RebindingList>>,
+    hint_query: Query<Entity, With<RebindingHint>>,
+    screen_query: Query<Entity, With<RebindingScreen>>,
+) {
+    if !contexts.is_active(InputContext::Menu) {
+        return;
+    }
+    let (list_entity, mut list) = match list_query.get_single_mut() {
+        Ok(list) => list,
+        Err(_) => return,
+    };
+    let screen = screen_query.single();
+
+    if let Some(action) = rebinding.waiting {
+        let key = match keyboard.get_just_pressed().next() {
+            Some(key) => *key,
+            None => return,
+        };
+        bindings.rebind(action, key);
+        // Keep the new binding from firing until the key is pressed again
+        keyboard.reset(key);
+        rebinding.waiting = None;
+
+        commands.entity(list_entity).despawn_recursive();
+        let list = spawn_bindings_list(&mut commands, &ascii, &bindings, list.selected);
+        commands.entity(screen).add_child(list);
+    } else if actions.just_pressed(Action::MoveUp) {
+        list.select_previous();
+        return;
+    } else if actions.just_pressed(Action::MoveDown) {
+        list.select_next();
+        return;
+    } else if actions.just_pressed(Action::Confirm) {
+        rebinding.waiting = Some(Action::ALL[list.selected]);
+    } else if actions.clear_just_pressed(Action::Cancel) {
+        // Cleared so the options screen underneath doesn't close on the same press, and
+        // Pause for the same reason since Escape is bound to both
+        actions.clear_just_pressed(Action::Pause);
+        if let Err(err) = bindings.save(INPUT_CONFIG_PATH) {
+            warn!("Could not save key bindings: {}", err);
+        }
+        if let Err(err) = state.pop() {
+            warn!("Could not close key bindings: {:?}", err);
+        }
+        return;
+    } else {
+        return;
+    }
+
+    for hint in hint_query.iter() {
+        commands.entity(hint).despawn_recursive();
+    }
+    let hint = spawn_hint(&mut commands, &ascii, rebinding.waiting);
+    commands.entity(screen).add_child(hint);
+}
+
+fn despawn_rebinding_screen(
+    mut commands: Commands,
+    mut contexts: ResMut<InputContexts>,
+    screen_query: Query<Entity, With<RebindingScreen>>,
+) {
+    contexts.pop(InputContext::Menu);
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,278 @@
+use std::{fs, path::Path};
+
+use bevy::{app::AppExit, ecs::schedule::ShouldRun, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controls::{Action, StepActions},
+    player::MovementMode,
+    GameConfig, FIXED_UPDATE,
+};
+
+// A running recording is written out at least this often, in fixed steps, so a crash
+// still leaves a file behind
+const FLUSH_INTERVAL: u64 = 60;
+
+pub struct ReplayPlugin;
+
+// Fixed simulation steps since startup, the clock replays are keyed on
+#[derive(Resource, Default)]
+pub struct FrameCount(pub u64);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub movement_mode: MovementMode,
+    // Only frames where the set of held actions changed
+    pub frames: Vec<ReplayFrame>,
+    pub length: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub frame: u64,
+    pub pressed: Vec<Action>,
+}
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    path: String,
+    pub replay: Replay,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    next: usize,
+    held: Vec<Action>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        ron::from_str(&contents).map_err(|err| err.to_string())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    // Returns whether the held actions changed on this frame
+    pub fn record(&mut self, frame: u64, pressed: Vec<Action>) -> bool {
+        self.length = frame;
+        let last = self.frames.last().map(|frame| frame.pressed.as_slice());
+        if last.unwrap_or(&[]) == pressed.as_slice() {
+            return false;
+        }
+        self.frames.push(ReplayFrame { frame, pressed });
+        true
+    }
+}
+
+impl ReplayRecorder {
+    pub fn new(path: &str, seed: u64, movement_mode: MovementMode) -> Self {
+        Self {
+            path: path.to_string(),
+            replay: Replay {
+                seed,
+                movement_mode,
+                ..default()
+            },
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        self.replay.save(&self.path)
+    }
+
+    fn flush(&self) {
+        if let Err(err) = self.save() {
+            warn!("Could not save replay to {}: {}", self.path, err);
+        }
+    }
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next: 0,
+            held: Vec::new(),
+        }
+    }
+
+    pub fn finished(&self, frame: u64) -> bool {
+        frame > self.replay.length
+    }
+
+    // Actions held on this frame
+    fn advance(&mut self, frame: u64) -> &[Action] {
+        while let Some(recorded) = self.replay.frames.get(self.next) {
+            if recorded.frame > frame {
+                break;
+            }
+            self.held = recorded.pressed.clone();
+            self.next += 1;
+        }
+        &self.held
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        // Actions are read once per frame but recorded and played back once per fixed step
+        app.init_resource::<FrameCount>()
+            .add_system_to_stage(CoreStage::PreUpdate, hold_replayed_actions.after("actions"))
+            // The step's actions are settled before anything in the step reads them
+            .add_system_to_stage(FIXED_UPDATE, count_frames.at_start().label("frame"))
+            .add_system_to_stage(FIXED_UPDATE, play_actions.at_start().after("frame"))
+            .add_system_to_stage(
+                FIXED_UPDATE,
+                record_actions.at_start().after("frame").after(play_actions),
+            )
+            .add_system_to_stage(CoreStage::Last, exit_after_max_frames.label("exit"))
+            .add_system_to_stage(CoreStage::Last, save_recording.after("exit"));
+    }
+}
+
+// Run criteria for systems that read the keyboard directly. What they do isn't part of the
+// recorded actions, so they are switched off while recording or playing a replay.
+pub fn not_replaying(
+    recorder: Option<Res<ReplayRecorder>>,
+    player: Option<Res<ReplayPlayer>>,
+) -> ShouldRun {
+    if recorder.is_none() && player.is_none() {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn count_frames(mut frame: ResMut<FrameCount>) {
+    frame.0 += 1;
+}
+
+fn record_actions(
+    recorder: Option<ResMut<ReplayRecorder>>,
+    frame: Res<FrameCount>,
+    actions: Res<StepActions>,
+) {
+    let mut recorder = match recorder {
+        Some(recorder) => recorder,
+        None => return,
+    };
+    let pressed = Action::ALL
+        .into_iter()
+        .filter(|action| actions.pressed(*action))
+        .collect();
+
+    if recorder.replay.record(frame.0, pressed) || frame.0.is_multiple_of(FLUSH_INTERVAL) {
+        recorder.flush();
+    }
+}
+
+// Replaces whatever was read from the devices with the actions held on the last step, for
+// the screens outside the fixed step
+fn hold_replayed_actions(player: Option<Res<ReplayPlayer>>, mut actions: ResMut<Input<Action>>) {
+    if let Some(player) = player {
+        actions.reset_all();
+        for action in &player.held {
+            actions.press(*action);
+        }
+        actions.clear();
+    }
+}
+
+// Sets the step actions to what was held on this step while recording
+fn play_actions(
+    mut commands: Commands,
+    player: Option<ResMut<ReplayPlayer>>,
+    frame: Res<FrameCount>,
+    mut actions: ResMut<StepActions>,
+) {
+    let mut player = match player {
+        Some(player) => player,
+        None => return,
+    };
+    if player.finished(frame.0) {
+        info!("Replay finished after {} frames", player.replay.length);
+        commands.remove_resource::<ReplayPlayer>();
+        return;
+    }
+
+    let held = player.advance(frame.0);
+    for action in Action::ALL {
+        if held.contains(&action) {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
+
+fn save_recording(recorder: Option<Res<ReplayRecorder>>, mut exit_event: EventReader<AppExit>) {
+    if let Some(recorder) = recorder {
+        if exit_event.iter().next().is_some() {
+            match recorder.save() {
+                Ok(()) => info!("Saved replay to {}", recorder.path),
+                Err(err) => warn!("Could not save replay to {}: {}", recorder.path, err),
+            }
+        }
+    }
+}
+
+fn exit_after_max_frames(
+    config: Res<GameConfig>,
+    frame: Res<FrameCount>,
+    mut exit_event: EventWriter<AppExit>,
+) {
+    if config.max_frames.is_some_and(|max| frame.0 >= max) {
+        exit_event.send(AppExit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changes_are_recorded() {
+        let mut replay = Replay::default();
+        replay.record(1, vec![]);
+        replay.record(2, vec![Action::MoveUp]);
+        replay.record(3, vec![Action::MoveUp]);
+        replay.record(4, vec![]);
+        assert_eq!(
+            replay.frames,
+            vec![
+                ReplayFrame {
+                    frame: 2,
+                    pressed: vec![Action::MoveUp]
+                },
+                ReplayFrame {
+                    frame: 4,
+                    pressed: vec![]
+                },
+            ]
+        );
+        assert_eq!(replay.length, 4);
+    }
+
+    #[test]
+    fn playback_holds_actions_between_changes() {
+        let mut replay = Replay::default();
+        replay.record(2, vec![Action::Confirm]);
+        replay.record(5, vec![]);
+        let mut player = ReplayPlayer::new(replay);
+
+        assert!(player.advance(1).is_empty());
+        assert_eq!(player.advance(3), &[Action::Confirm]);
+        assert!(player.advance(5).is_empty());
+        assert!(player.finished(6));
+    }
+}
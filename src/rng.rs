@@ -0,0 +1,130 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{prelude::*, utils::HashMap};
+
+// Each subsystem draws from its own stream, so adding a roll in one never shifts another
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    Encounters,
+    Combat,
+    Loot,
+    MapGen,
+}
+
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    streams: HashMap<RngStream, Rng>,
+}
+
+// xorshift64*, small and reproducible from a seed
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(seed_from_clock())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::default(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut Rng {
+        let seed = self.seed;
+        self.streams
+            .entry(stream)
+            .or_insert_with(|| Rng::new(derive_seed(seed, stream)))
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+}
+
+pub fn seed_from_clock() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+// splitmix64 of the game seed mixed with the stream, so nearby seeds still give unrelated streams
+fn derive_seed(seed: u64, stream: RngStream) -> u64 {
+    let mut z = seed ^ (stream as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(rng: &mut Rng, count: usize) -> Vec<u64> {
+        (0..count).map(|_| rng.next_u64()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = GameRng::new(99);
+        let mut b = GameRng::new(99);
+        assert_eq!(
+            draw(a.stream(RngStream::Combat), 10),
+            draw(b.stream(RngStream::Combat), 10)
+        );
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let mut untouched = GameRng::new(5);
+        let mut busy = GameRng::new(5);
+        draw(busy.stream(RngStream::Combat), 100);
+        assert_eq!(
+            draw(untouched.stream(RngStream::MapGen), 10),
+            draw(busy.stream(RngStream::MapGen), 10)
+        );
+        assert_ne!(
+            draw(GameRng::new(5).stream(RngStream::Loot), 10),
+            draw(GameRng::new(5).stream(RngStream::Encounters), 10)
+        );
+    }
+
+    #[test]
+    fn floats_stay_in_unit_range() {
+        let mut rng = Rng::new(0);
+        for _ in 0..1000 {
+            let value = rng.next_f32();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::{fs, path::Path};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{combat::CombatStats, player::Player, GameConfig, GameState};
+
+pub const SAVE_PATH: &str = "saves/save.ron";
+
+pub struct SavePlugin;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub map_path: String,
+    pub position: Vec3,
+    pub experience: usize,
+    pub health: isize,
+}
+
+// The save the running game was continued from, or None for a new game
+#[derive(Resource, Default)]
+pub struct CurrentSave(pub Option<SaveGame>);
+
+impl SaveGame {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        ron::from_str(&contents).map_err(|err| err.to_string())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentSave>()
+            .add_system_set(SystemSet::on_enter(GameState::Overworld).with_system(autosave))
+            .add_system_to_stage(CoreStage::Last, save_on_exit.after("exit"));
+    }
+}
+
+fn snapshot(
+    config: &GameConfig,
+    current: &CurrentSave,
+    player_query: &Query<(&Transform, &Player, &CombatStats)>,
+) -> Option<SaveGame> {
+    let (transform, player, stats) = player_query.get_single().ok()?;
+    let map_path = current
+        .0
+        .as_ref()
+        .map_or(&config.map_path, |save| &save.map_path);
+    Some(SaveGame {
+        map_path: map_path.clone(),
+        position: transform.translation,
+        experience: player.experience,
+        health: stats.health,
+    })
+}
+
+fn write_save(config: &GameConfig, save: &SaveGame) {
+    if let Some(path) = &config.save_path {
+        if let Err(err) = save.save(path) {
+            warn!("Could not save the game to {}: {}", path, err);
+        }
+    }
+}
+
+// Saves whenever the player is back on the map, after starting a game or winning a fight
+fn autosave(
+    config: Res<GameConfig>,
+    current: Res<CurrentSave>,
+    player_query: Query<(&Transform, &Player, &CombatStats)>,
+) {
+    if let Some(save) = snapshot(&config, &current, &player_query) {
+        write_save(&config, &save);
+    }
+}
+
+fn save_on_exit(
+    config: Res<GameConfig>,
+    current: Res<CurrentSave>,
+    state: Res<State<GameState>>,
+    player_query: Query<(&Transform, &Player, &CombatStats)>,
+    mut exit_event: EventReader<AppExit>,
+) {
+    if exit_event.iter().next().is_none() {
+        return;
+    }
+    // Leaving mid-battle would let the fight be skipped by reloading
+    if state.current() == &GameState::Combat || state.inactives().contains(&GameState::Combat) {
+        return;
+    }
+    if let Some(save) = snapshot(&config, &current, &player_query) {
+        write_save(&config, &save);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_ron() {
+        let save = SaveGame {
+            map_path: "assets/map.txt".to_string(),
+            position: Vec3::new(0.3, -0.2, 900.0),
+            experience: 17,
+            health: 6,
+        };
+        let text = ron::to_string(&save).unwrap();
+        assert_eq!(ron::from_str::<SaveGame>(&text).unwrap(), save);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{fs, path::Path};
+
+use bevy::{
+    prelude::*,
+    window::{PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::hud::HudRoot;
+
+pub const SETTINGS_PATH: &str = "config/settings.ron";
+
+pub const RESOLUTIONS: [(f32, f32); 4] = [
+    (1280.0, 720.0),
+    (1600.0, 900.0),
+    (1920.0, 1080.0),
+    (2560.0, 1440.0),
+];
+pub const UI_SCALES: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+
+pub struct SettingsPlugin;
+
+// How the fixed 16:9 play area is fitted into a window of any shape
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScalingStrategy {
+    // Keeps the play area's aspect ratio and fills the rest with bars
+    #[default]
+    Letterbox,
+    // Shows more of the world on the longer side
+    Expand,
+    // Draws every glyph pixel as a whole number of screen pixels
+    PixelPerfect,
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub width: f32,
+    pub height: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub resizable: bool,
+    pub ui_scale: f32,
+    pub scaling: ScalingStrategy,
+}
+
+// A window size given on the command line. It wins over the settings for this run only and
+// is never saved; picking a resolution in the options drops it.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct WindowSizeOverride(pub Option<Vec2>);
+
+impl WindowSizeOverride {
+    pub fn size(&self, settings: &Settings) -> Vec2 {
+        self.0
+            .unwrap_or_else(|| Vec2::new(settings.width, settings.height))
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: 1600.0,
+            height: 900.0,
+            fullscreen: false,
+            vsync: true,
+            resizable: true,
+            ui_scale: 1.0,
+            scaling: ScalingStrategy::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        ron::from_str(&contents).map_err(|err| err.to_string())
+    }
+
+    pub fn load_or_default() -> Self {
+        Settings::load(SETTINGS_PATH).unwrap_or_else(|err| {
+            info!("Using default settings ({}: {})", SETTINGS_PATH, err);
+            Settings::default()
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        WindowDescriptor {
+            width: self.width,
+            height: self.height,
+            title: "Bevy Tutorial".to_string(),
+            resizable: self.resizable,
+            present_mode: self.present_mode(),
+            mode: self.window_mode(),
+            ..default()
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    pub fn cycle_resolution(&mut self, forward: bool) {
+        let current = RESOLUTIONS
+            .iter()
+            .position(|&(width, height)| width == self.width && height == self.height);
+        (self.width, self.height) = RESOLUTIONS[cycle_index(current, RESOLUTIONS.len(), forward)];
+    }
+
+    pub fn cycle_ui_scale(&mut self, forward: bool) {
+        let current = UI_SCALES
+            .iter()
+            .position(|scale| (scale - self.ui_scale).abs() < 0.01);
+        self.ui_scale = UI_SCALES[cycle_index(current, UI_SCALES.len(), forward)];
+    }
+
+    pub fn cycle_scaling(&mut self, forward: bool) {
+        let all = ScalingStrategy::ALL;
+        let current = all.iter().position(|&scaling| scaling == self.scaling);
+        self.scaling = all[cycle_index(current, all.len(), forward)];
+    }
+}
+
+impl ScalingStrategy {
+    pub const ALL: [ScalingStrategy; 3] = [
+        ScalingStrategy::Letterbox,
+        ScalingStrategy::Expand,
+        ScalingStrategy::PixelPerfect,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScalingStrategy::Letterbox => "Letterbox",
+            ScalingStrategy::Expand => "Expand",
+            ScalingStrategy::PixelPerfect => "Pixel",
+        }
+    }
+}
+
+// A value that isn't one of the presets, e.g. set by hand in the file, starts over at the first
+fn cycle_index(current: Option<usize>, len: usize, forward: bool) -> usize {
+    match current {
+        Some(index) if forward => (index + 1) % len,
+        Some(index) => (index + len - 1) % len,
+        None => 0,
+    }
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<Settings>() {
+            app.insert_resource(Settings::load_or_default());
+        }
+        app.init_resource::<WindowSizeOverride>()
+            .add_system(apply_window_settings)
+            .add_system(apply_ui_scale);
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    size_override: Res<WindowSizeOverride>,
+    mut windows: Option<ResMut<Windows>>,
+) {
+    if !settings.is_changed() && !size_override.is_changed() {
+        return;
+    }
+    if let Some(window) = windows
+        .as_mut()
+        .and_then(|windows| windows.get_primary_mut())
+    {
+        window.set_mode(settings.window_mode());
+        window.set_present_mode(settings.present_mode());
+        window.set_resizable(settings.resizable);
+        if !settings.fullscreen {
+            let size = size_override.size(&settings);
+            window.set_resolution(size.x, size.y);
+        }
+    }
+}
+
+fn apply_ui_scale(settings: Res<Settings>, mut hud_query: Query<&mut Transform, With<HudRoot>>) {
+    if settings.is_changed() {
+        for mut transform in hud_query.iter_mut() {
+            transform.scale = Vec3::new(settings.ui_scale, settings.ui_scale, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_cycles_through_presets() {
+        let mut settings = Settings::default();
+        settings.cycle_resolution(true);
+        assert_eq!((settings.width, settings.height), (1920.0, 1080.0));
+        settings.cycle_resolution(true);
+        settings.cycle_resolution(true);
+        assert_eq!((settings.width, settings.height), RESOLUTIONS[0]);
+        settings.cycle_resolution(false);
+        assert_eq!((settings.width, settings.height), RESOLUTIONS[3]);
+    }
+
+    #[test]
+    fn unknown_values_start_over() {
+        let mut settings = Settings {
+            width: 1000.0,
+            ui_scale: 3.0,
+            ..default()
+        };
+        settings.cycle_resolution(false);
+        settings.cycle_ui_scale(true);
+        assert_eq!((settings.width, settings.height), RESOLUTIONS[0]);
+        assert_eq!(settings.ui_scale, UI_SCALES[0]);
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let settings: Settings = ron::from_str("(fullscreen: true)").unwrap();
+        assert!(settings.fullscreen);
+        assert_eq!(settings.width, Settings::default().width);
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    controls::InputBindings,
+    headless::{add_headless_plugins, FRAME_TIME},
+    player::Player,
+    settings::Settings,
+    GameConfig, GamePlugin, GameState,
+};
+
+// The game without a window or renderer, stepped one fixed frame at a time
+pub struct TestApp {
+    pub app: App,
+    now: Instant,
+}
+
+impl TestApp {
+    // Skips the main menu and starts a new game
+    pub fn new(seed: u64) -> Self {
+        Self::with_config(GameConfig {
+            seed: Some(seed),
+            start_state: GameState::Overworld,
+            ..default()
+        })
+    }
+
+    pub fn with_config(mut config: GameConfig) -> Self {
+        // Tests must never overwrite the save on this machine
+        config.save_path = None;
+        let mut app = App::new();
+        // Settings saved on this machine must not change the HUD scale or layout
+        add_headless_plugins(&mut app)
+            .insert_resource(Settings::default())
+            .add_plugin(GamePlugin { config });
+
+        let now = app.world.resource::<Time>().startup();
+        let mut test_app = Self { app, now };
+        test_app.step();
+        // Bindings saved on this machine must not change what the tests press
+        test_app.app.insert_resource(InputBindings::default());
+        test_app
+    }
+
+    pub fn step(&mut self) {
+        self.step_by(FRAME_TIME);
+    }
+
+    // One frame of the given length, which may run any number of fixed steps
+    pub fn step_by(&mut self, seconds: f32) {
+        self.now += Duration::from_secs_f32(seconds);
+        self.app
+            .insert_resource(TimeUpdateStrategy::ManualInstant(self.now));
+        self.app.update();
+    }
+
+    pub fn run_for(&mut self, seconds: f32) {
+        for _ in 0..(seconds / FRAME_TIME).round() as usize {
+            self.step();
+        }
+    }
+
+    // Steps until the condition holds, giving up after max_seconds
+    pub fn run_until(&mut self, max_seconds: f32, mut done: impl FnMut(&mut Self) -> bool) -> bool {
+        for _ in 0..(max_seconds / FRAME_TIME).round() as usize {
+            if done(self) {
+                return true;
+            }
+            self.step();
+        }
+        done(self)
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    fn send_key(&mut self, key: KeyCode, state: ButtonState) {
+        self.app.world.send_event(KeyboardInput {
+            scan_code: 0,
+            key_code: Some(key),
+            state,
+        });
+    }
+
+    pub fn state(&self) -> GameState {
+        *self.app.world.resource::<State<GameState>>().current()
+    }
+
+    pub fn player_translation(&mut self) -> Vec3 {
+        self.app
+            .world
+            .query_filtered::<&Transform, With<Player>>()
+            .single(&self.app.world)
+            .translation
+    }
+}
@@ -6,8 +6,10 @@ use std::{
 use bevy::prelude::*;
 
 use crate::{
-    ascii::{spawn_ascii_sprite, AsciiSheet},
-    GameState, TILE_SIZE,
+    ascii::{spawn_ascii_char, AsciiSheet},
+    encounter::EncounterSettings,
+    save::CurrentSave,
+    GameConfig, GameState, TILE_SIZE,
 };
 
 pub struct TileMapPlugin;
@@ -16,14 +18,16 @@ pub struct TileMapPlugin;
 pub struct Map;
 
 #[derive(Component)]
-pub struct EncounterSpawner;
+pub struct EncounterSpawner {
+    pub rate: f32,
+}
 
 #[derive(Component)]
 pub struct TileCollider;
 
 impl Plugin for TileMapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(create_simple_map)
+        app.add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(create_simple_map))
             .add_system_set(SystemSet::on_enter(GameState::Overworld).with_system(show_map))
             .add_system_set(SystemSet::on_exit(GameState::Overworld).with_system(hide_map));
     }
@@ -55,17 +59,28 @@ fn show_map(
     }
 }
 
-fn create_simple_map(mut commands: Commands, ascii: Res<AsciiSheet>) {
-    let file = File::open("assets/map.txt").expect("No map file");
+// Tiles start hidden until the overworld is entered, like the player
+fn create_simple_map(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    config: Res<GameConfig>,
+    current: Res<CurrentSave>,
+    encounter_settings: Res<EncounterSettings>,
+) {
+    let map_path = current
+        .0
+        .as_ref()
+        .map_or(&config.map_path, |save| &save.map_path);
+    let file = File::open(map_path).expect("No map file");
     let mut tiles = Vec::new();
 
     for (y, line) in BufReader::new(file).lines().enumerate() {
         if let Ok(line) = line {
             for (x, char) in line.chars().enumerate() {
-                let tile = spawn_ascii_sprite(
+                let tile = spawn_ascii_char(
                     &mut commands,
                     &ascii,
-                    char as usize,
+                    char,
                     Color::rgb(0.9, 0.9, 0.9),
                     Vec3 {
                         x: x as f32 * TILE_SIZE,
@@ -74,11 +89,12 @@ fn create_simple_map(mut commands: Commands, ascii: Res<AsciiSheet>) {
                     },
                     Vec3::splat(1.0),
                 );
+                commands.entity(tile).insert(Visibility::INVISIBLE);
                 if char == '#' {
                     commands.entity(tile).insert(TileCollider);
                 }
-                if char == '~' {
-                    commands.entity(tile).insert(EncounterSpawner);
+                if let Some(rate) = encounter_settings.zone_rate(char) {
+                    commands.entity(tile).insert(EncounterSpawner { rate });
                 }
                 tiles.push(tile);
             }
@@ -0,0 +1,460 @@
+use std::{fmt, time::Duration};
+
+use bevy::{ecs::schedule::StateError, prelude::*};
+
+use crate::{
+    ascii::AsciiSheet,
+    camera::VisibleArea,
+    controls::{InputContext, InputContexts},
+    GameState, FIXED_TIME_STEP, FIXED_UPDATE, TILE_SIZE,
+};
+
+pub struct TransitionPlugin;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionEffect {
+    Fade,
+    Wipe,
+    Dissolve,
+    Iris,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    pub duration: f32,
+    pub easing: Easing,
+    pub color: Color,
+    pub next_state: GameState,
+}
+
+#[derive(Resource, Default)]
+pub struct Transitions {
+    active: Option<ActiveTransition>,
+    // Half size of the area the grid effects have to cover
+    area: VisibleArea,
+}
+
+struct ActiveTransition {
+    transition: Transition,
+    timer: Timer,
+    sent: bool,
+    root: Entity,
+}
+
+pub struct TransitionStarted {
+    pub next_state: GameState,
+}
+
+pub struct TransitionMidpoint {
+    pub next_state: GameState,
+}
+
+pub struct TransitionFinished {
+    pub next_state: GameState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionError {
+    AlreadyRunning,
+}
+
+#[derive(Component)]
+struct TransitionRoot;
+
+#[derive(Component)]
+struct TransitionCell {
+    threshold: f32,
+    glyph: usize,
+}
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Transitions>()
+            .add_event::<TransitionStarted>()
+            .add_event::<TransitionMidpoint>()
+            .add_event::<TransitionFinished>()
+            .add_system(track_visible_area)
+            // Timed in fixed steps so replays change state on the same step as the recording
+            .add_system_to_stage(FIXED_UPDATE, update_transitions.label("transitions"));
+    }
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransitionError::AlreadyRunning => write!(f, "a screen transition is already running"),
+        }
+    }
+}
+
+impl Transitions {
+    pub fn is_running(&self) -> bool {
+        self.active.is_some()
+    }
+
+    pub fn start(
+        &mut self,
+        commands: &mut Commands,
+        ascii: &AsciiSheet,
+        transition: Transition,
+    ) -> Result<(), TransitionError> {
+        if self.is_running() {
+            return Err(TransitionError::AlreadyRunning);
+        }
+
+        // Input is locked as soon as the commands are applied, not when the transition
+        // first updates, so nothing queued behind the request can still act on it
+        let next_state = transition.next_state;
+        commands.add(move |world: &mut World| {
+            if let Some(mut contexts) = world.get_resource_mut::<InputContexts>() {
+                contexts.push(InputContext::Transition);
+            }
+            world.send_event(TransitionStarted { next_state });
+        });
+
+        self.active = Some(ActiveTransition {
+            transition,
+            timer: Timer::from_seconds(transition.duration, TimerMode::Once),
+            sent: false,
+            root: spawn_transition(commands, ascii, &transition, self.area.half_size),
+        });
+        Ok(())
+    }
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+        }
+    }
+}
+
+impl Transition {
+    pub fn new(effect: TransitionEffect, next_state: GameState) -> Self {
+        Self {
+            effect,
+            duration: 1.0,
+            easing: Easing::Linear,
+            color: Color::rgb(0.1, 0.1, 0.15),
+            next_state,
+        }
+    }
+
+    pub fn fade(next_state: GameState) -> Self {
+        Self::new(TransitionEffect::Fade, next_state)
+    }
+
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.duration = seconds;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    // How much of the screen is covered at `percent` through the transition,
+    // rising to 1 at the midpoint and falling back to 0 at the end
+    fn coverage(&self, percent: f32) -> f32 {
+        let phase = if percent < 0.5 {
+            percent * 2.0
+        } else {
+            (1.0 - percent) * 2.0
+        };
+        self.easing.apply(phase)
+    }
+}
+
+// Kept on the resource so transitions started with only Commands still cover the whole view
+fn track_visible_area(area: Res<VisibleArea>, mut transitions: ResMut<Transitions>) {
+    if area.is_changed() {
+        transitions.area = *area;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_transitions(
+    mut commands: Commands,
+    mut transitions: ResMut<Transitions>,
+    mut root_query: Query<(&mut Transform, &Children), With<TransitionRoot>>,
+    mut cell_query: Query<(&TransitionCell, &mut TextureAtlasSprite, &mut Visibility)>,
+    camera_query: Query<&Transform, (With<Camera>, Without<TransitionRoot>)>,
+    mut state: ResMut<State<GameState>>,
+    mut midpoint_event: EventWriter<TransitionMidpoint>,
+    mut finished_event: EventWriter<TransitionFinished>,
+) {
+    let active = match transitions.active.as_mut() {
+        Some(active) => active,
+        None => return,
+    };
+    let next_state = active.transition.next_state;
+
+    active.timer.tick(Duration::from_secs_f32(FIXED_TIME_STEP));
+    let percent = active.timer.percent();
+    let coverage = active.transition.coverage(percent);
+    let out = percent >= 0.5;
+
+    if let Ok((mut transform, children)) = root_query.get_mut(active.root) {
+        if let Ok(camera_transform) = camera_query.get_single() {
+            transform.translation.x = camera_transform.translation.x;
+            transform.translation.y = camera_transform.translation.y;
+        }
+
+        for child in children.iter() {
+            if let Ok((cell, mut sprite, mut visibility)) = cell_query.get_mut(*child) {
+                let threshold = if out && active.transition.effect == TransitionEffect::Wipe {
+                    1.0 - cell.threshold
+                } else {
+                    cell.threshold
+                };
+                visibility.is_visible = coverage > threshold;
+
+                match active.transition.effect {
+                    TransitionEffect::Fade => {
+                        sprite.color.set_a(coverage);
+                    }
+                    TransitionEffect::Dissolve if coverage < threshold + 0.2 => {
+                        sprite.index = cell.glyph;
+                    }
+                    _ => sprite.index = 0,
+                };
+            }
+        }
+    }
+
+    // While paused no fixed steps run, but the state change is also held back in case the
+    // pause was requested this very frame
+    if percent > 0.5 && !active.sent && state.current() != &GameState::Paused {
+        match state.set(next_state) {
+            Ok(()) => {
+                midpoint_event.send(TransitionMidpoint { next_state });
+                active.sent = true;
+            }
+            Err(StateError::StateAlreadyQueued) => {}
+            Err(err) => {
+                warn!("Transition could not change state: {:?}", err);
+                active.sent = true;
+            }
+        }
+    }
+
+    if active.timer.just_finished() {
+        commands.entity(active.root).despawn_recursive();
+        transitions.active = None;
+        finished_event.send(TransitionFinished { next_state });
+    }
+}
+
+fn spawn_transition(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    transition: &Transition,
+    half_size: Vec2,
+) -> Entity {
+    let mut cells = Vec::new();
+
+    if transition.effect == TransitionEffect::Fade {
+        let mut sprite = TextureAtlasSprite::new(0);
+        sprite.color = transition.color;
+        sprite.color.set_a(0.0);
+        sprite.custom_size = Some(Vec2::splat(100000.0));
+
+        cells.push(
+            commands
+                .spawn(SpriteSheetBundle {
+                    sprite,
+                    texture_atlas: ascii.handle.clone(),
+                    ..Default::default()
+                })
+                .insert(TransitionCell {
+                    threshold: 0.0,
+                    glyph: 0,
+                })
+                .id(),
+        );
+    } else {
+        let columns = (2.0 * half_size.x / TILE_SIZE).ceil() as usize + 1;
+        let rows = (2.0 * half_size.y / TILE_SIZE).ceil() as usize + 1;
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let mut sprite = TextureAtlasSprite::new(0);
+                sprite.color = transition.color;
+                sprite.custom_size = Some(Vec2::splat(TILE_SIZE));
+
+                let cell = commands
+                    .spawn(SpriteSheetBundle {
+                        sprite,
+                        texture_atlas: ascii.handle.clone(),
+                        transform: Transform::from_xyz(
+                            (column as f32 - (columns - 1) as f32 / 2.0) * TILE_SIZE,
+                            ((rows - 1) as f32 / 2.0 - row as f32) * TILE_SIZE,
+                            0.0,
+                        ),
+                        visibility: Visibility::INVISIBLE,
+                        ..Default::default()
+                    })
+                    .insert(TransitionCell {
+                        threshold: cell_threshold(transition.effect, column, row, columns, rows),
+                        glyph: dissolve_glyph(cell_noise(column, row), ascii.glyphs),
+                    })
+                    .id();
+                cells.push(cell);
+            }
+        }
+    }
+
+    // Over the world and the HUD, but under the pause screen and the menus it opens, which
+    // can come up mid-transition
+    commands
+        .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+            0.0, 0.0, 920.0,
+        )))
+        .insert(TransitionRoot)
+        .insert(Name::new("Transition"))
+        .push_children(&cells)
+        .id()
+}
+
+// Coverage at which a cell appears while the transition closes in, always
+// below 1 so the screen is fully covered at the midpoint
+fn cell_threshold(
+    effect: TransitionEffect,
+    column: usize,
+    row: usize,
+    columns: usize,
+    rows: usize,
+) -> f32 {
+    match effect {
+        TransitionEffect::Fade => 0.0,
+        TransitionEffect::Wipe => column as f32 / columns as f32,
+        TransitionEffect::Dissolve => (cell_noise(column, row) % 1000) as f32 / 1000.0 * 0.8,
+        TransitionEffect::Iris => {
+            let center = Vec2::new(columns as f32 - 1.0, rows as f32 - 1.0) / 2.0;
+            let offset = (Vec2::new(column as f32, row as f32) - center) / center.max(Vec2::ONE);
+            (1.0 - offset.length() / std::f32::consts::SQRT_2).clamp(0.0, 0.99)
+        }
+    }
+}
+
+// One of the CP437 shade and box glyphs, or the solid cell if the sheet doesn't have them
+fn dissolve_glyph(noise: u32, glyphs: usize) -> usize {
+    const FIRST: usize = 176;
+    const COUNT: usize = 48;
+    if glyphs >= FIRST + COUNT {
+        FIRST + noise as usize % COUNT
+    } else {
+        0
+    }
+}
+
+fn cell_noise(column: usize, row: usize) -> u32 {
+    let mut hash =
+        (column as u32).wrapping_mul(0x9E37_79B9) ^ (row as u32).wrapping_mul(0x85EB_CA6B);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7FEB_352D);
+    hash ^= hash >> 15;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+
+    use super::*;
+    use crate::cp437::Cp437Mapping;
+
+    #[test]
+    fn rejects_overlapping_transitions() {
+        let world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let ascii = AsciiSheet {
+            handle: Handle::default(),
+            mapping: Cp437Mapping::default(),
+            glyphs: 256,
+            cell_size: Vec2::splat(9.0),
+        };
+
+        let mut transitions = Transitions::default();
+        assert_eq!(
+            transitions.start(&mut commands, &ascii, Transition::fade(GameState::Combat)),
+            Ok(())
+        );
+        assert!(transitions.is_running());
+        assert_eq!(
+            transitions.start(
+                &mut commands,
+                &ascii,
+                Transition::fade(GameState::Overworld)
+            ),
+            Err(TransitionError::AlreadyRunning)
+        );
+    }
+
+    #[test]
+    fn easing_endpoints() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn coverage_peaks_at_midpoint() {
+        let transition = Transition::fade(GameState::Combat);
+        assert_eq!(transition.coverage(0.0), 0.0);
+        assert_eq!(transition.coverage(0.25), 0.5);
+        assert_eq!(transition.coverage(0.5), 1.0);
+        assert_eq!(transition.coverage(0.75), 0.5);
+        assert_eq!(transition.coverage(1.0), 0.0);
+    }
+
+    #[test]
+    fn dissolve_glyphs_stay_inside_small_sheets() {
+        for noise in [0, 47, 1000, u32::MAX] {
+            assert!((176..224).contains(&dissolve_glyph(noise, 256)));
+            assert_eq!(dissolve_glyph(noise, 128), 0);
+        }
+    }
+
+    #[test]
+    fn iris_closes_from_the_edges() {
+        let corner = cell_threshold(TransitionEffect::Iris, 0, 0, 9, 9);
+        let center = cell_threshold(TransitionEffect::Iris, 4, 4, 9, 9);
+        assert!(corner < 0.01);
+        assert!(center > 0.9 && center < 1.0);
+    }
+}
@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+
+use crate::{
+    ascii::{spawn_ascii_char, spawn_ascii_sprite, spawn_ascii_text, AsciiChar, AsciiSheet},
+    TILE_SIZE,
+};
+
+pub struct WidgetPlugin;
+
+#[derive(Component)]
+pub struct AsciiPanel {
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Component)]
+pub struct AsciiList {
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+#[derive(Component)]
+struct ListCursor;
+
+#[derive(Component)]
+pub struct AsciiBar {
+    pub width: usize,
+    pub fraction: f32,
+}
+
+impl Plugin for WidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_list_cursor).add_system(update_bars);
+    }
+}
+
+impl AsciiList {
+    pub fn select_next(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + 1) % self.items.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&str> {
+        self.items.get(self.selected).map(|item| item.as_str())
+    }
+}
+
+pub fn spawn_ascii_panel(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    center: Vec3,
+    width: usize,
+    height: usize,
+    title: Option<&str>,
+) -> Entity {
+    let width = width.max(2);
+    let height = height.max(2);
+    let border_color = Color::rgb(0.8, 0.8, 0.8);
+    let mut sprites = Vec::new();
+
+    for row in 0..height {
+        for column in 0..width {
+            let translation = Vec3 {
+                x: (column as f32 - (width - 1) as f32 / 2.0) * TILE_SIZE,
+                y: ((height - 1) as f32 / 2.0 - row as f32) * TILE_SIZE,
+                z: 0.0,
+            };
+            let background = spawn_ascii_sprite(
+                commands,
+                ascii,
+                0,
+                Color::rgb(0.05, 0.05, 0.1),
+                translation,
+                Vec3::splat(1.0),
+            );
+            sprites.push(background);
+
+            if let Some(char) = panel_char(width, height, column, row, title) {
+                let glyph = spawn_ascii_char(
+                    commands,
+                    ascii,
+                    char,
+                    border_color,
+                    translation + Vec3::new(0.0, 0.0, 1.0),
+                    Vec3::splat(1.0),
+                );
+                sprites.push(glyph);
+            }
+        }
+    }
+
+    commands
+        .spawn(SpatialBundle::default())
+        .insert(Name::new("Panel"))
+        .insert(Transform::from_translation(center))
+        .insert(AsciiPanel { width, height })
+        .push_children(&sprites)
+        .id()
+}
+
+fn panel_char(
+    width: usize,
+    height: usize,
+    column: usize,
+    row: usize,
+    title: Option<&str>,
+) -> Option<char> {
+    let last_column = width - 1;
+    let last_row = height - 1;
+    if row == 0 && column >= 2 {
+        if let Some(char) = title.and_then(|title| title.chars().nth(column - 2)) {
+            if column < last_column {
+                return Some(char);
+            }
+        }
+    }
+    match (column, row) {
+        (0, 0) => Some('┌'),
+        (c, 0) if c == last_column => Some('┐'),
+        (0, r) if r == last_row => Some('└'),
+        (c, r) if c == last_column && r == last_row => Some('┘'),
+        (_, 0) => Some('─'),
+        (_, r) if r == last_row => Some('─'),
+        (0, _) => Some('│'),
+        (c, _) if c == last_column => Some('│'),
+        _ => None,
+    }
+}
+
+pub fn spawn_ascii_list(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    top_left: Vec3,
+    items: &[&str],
+) -> Entity {
+    let mut children = Vec::new();
+    for (row, item) in items.iter().enumerate() {
+        children.push(spawn_ascii_text(
+            commands,
+            ascii,
+            item,
+            list_row_position(row) + Vec3::new(2.0 * TILE_SIZE, 0.0, 0.0),
+        ));
+    }
+
+    let cursor = spawn_ascii_char(
+        commands,
+        ascii,
+        '►',
+        Color::rgb(0.9, 0.9, 0.3),
+        list_row_position(0),
+        Vec3::splat(1.0),
+    );
+    commands.entity(cursor).insert(ListCursor);
+    children.push(cursor);
+
+    commands
+        .spawn(SpatialBundle::default())
+        .insert(Name::new("List"))
+        .insert(Transform::from_translation(top_left))
+        .insert(AsciiList {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            selected: 0,
+        })
+        .push_children(&children)
+        .id()
+}
+
+fn list_row_position(row: usize) -> Vec3 {
+    Vec3::new(0.0, -(row as f32) * TILE_SIZE, 0.0)
+}
+
+fn update_list_cursor(
+    list_query: Query<(&AsciiList, &Children), Changed<AsciiList>>,
+    mut cursor_query: Query<&mut Transform, With<ListCursor>>,
+) {
+    for (list, children) in list_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut transform) = cursor_query.get_mut(*child) {
+                transform.translation = list_row_position(list.selected);
+            }
+        }
+    }
+}
+
+pub fn spawn_ascii_bar(
+    commands: &mut Commands,
+    ascii: &AsciiSheet,
+    center: Vec3,
+    width: usize,
+    fraction: f32,
+    color: Color,
+) -> Entity {
+    let mut segments = Vec::new();
+    for (i, char) in bar_chars(width, fraction).into_iter().enumerate() {
+        segments.push(spawn_ascii_char(
+            commands,
+            ascii,
+            char,
+            color,
+            Vec3::new((i as f32 - (width - 1) as f32 / 2.0) * TILE_SIZE, 0.0, 0.0),
+            Vec3::splat(1.0),
+        ));
+    }
+
+    commands
+        .spawn(SpatialBundle::default())
+        .insert(Name::new("Bar"))
+        .insert(Transform::from_translation(center))
+        .insert(AsciiBar { width, fraction })
+        .push_children(&segments)
+        .id()
+}
+
+pub fn bar_chars(width: usize, fraction: f32) -> Vec<char> {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let mut halves = (fraction * width as f32 * 2.0).round() as usize;
+    if fraction > 0.0 && halves == 0 {
+        halves = 1;
+    }
+
+    (0..width)
+        .map(|i| match halves.saturating_sub(i * 2) {
+            0 => '░',
+            1 => '▌',
+            _ => '█',
+        })
+        .collect()
+}
+
+fn update_bars(
+    ascii: Res<AsciiSheet>,
+    bar_query: Query<(&AsciiBar, &Children), Changed<AsciiBar>>,
+    mut segment_query: Query<(&mut TextureAtlasSprite, &mut AsciiChar)>,
+) {
+    for (bar, children) in bar_query.iter() {
+        let chars = bar_chars(bar.width, bar.fraction);
+        for (child, char) in children.iter().zip(chars) {
+            if let Ok((mut sprite, mut ascii_char)) = segment_query.get_mut(*child) {
+                sprite.index = ascii.glyph_index(ascii.mapping.index_of(char));
+                ascii_char.0 = char;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bar_fill() {
+        assert_eq!(bar_chars(4, 1.0), vec!['█'; 4]);
+        assert_eq!(bar_chars(4, 0.0), vec!['░'; 4]);
+        assert_eq!(bar_chars(4, 0.5), vec!['█', '█', '░', '░']);
+        assert_eq!(bar_chars(4, 0.375), vec!['█', '▌', '░', '░']);
+    }
+
+    #[test]
+    fn bar_never_looks_empty_while_alive() {
+        assert_eq!(bar_chars(10, 0.01), {
+            let mut chars = vec!['░'; 10];
+            chars[0] = '▌';
+            chars
+        });
+    }
+
+    #[test]
+    fn list_selection_wraps() {
+        let mut list = AsciiList {
+            items: vec!["Attack".to_string(), "Run".to_string()],
+            selected: 0,
+        };
+        list.select_previous();
+        assert_eq!(list.selected_item(), Some("Run"));
+        list.select_next();
+        assert_eq!(list.selected_item(), Some("Attack"));
+    }
+}
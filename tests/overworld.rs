@@ -0,0 +1,288 @@
+use bevy::prelude::*;
+use tutorial::{
+    combat::{escape_chance, CombatPhase, CombatStats, Enemy},
+    player::{MovementMode, Player},
+    rng::{GameRng, RngStream},
+    test_app::TestApp,
+    tilemap::TileCollider,
+    GameConfig, GameState, TILE_SIZE,
+};
+
+#[test]
+fn starts_in_overworld() {
+    let mut game = TestApp::new(1);
+    assert_eq!(game.state(), GameState::Overworld);
+
+    let walls = game
+        .app
+        .world
+        .query_filtered::<(), With<TileCollider>>()
+        .iter(&game.app.world)
+        .count();
+    assert!(walls > 0);
+
+    let player = game.player_translation();
+    assert!((player.x - 2.0 * TILE_SIZE).abs() < 1e-4);
+    assert!((player.y + 2.0 * TILE_SIZE).abs() < 1e-4);
+}
+
+#[test]
+fn new_game_from_the_main_menu() {
+    let mut game = TestApp::with_config(GameConfig {
+        seed: Some(1),
+        ..default()
+    });
+    assert_eq!(game.state(), GameState::MainMenu);
+    let players = game
+        .app
+        .world
+        .query_filtered::<(), With<Player>>()
+        .iter(&game.app.world)
+        .count();
+    assert_eq!(players, 0);
+
+    // New Game is the first item, and Continue is hidden without a save
+    game.press(KeyCode::Return);
+    game.step();
+    game.release(KeyCode::Return);
+    game.step();
+    assert_eq!(game.state(), GameState::Overworld);
+    let player = game.player_translation();
+    assert!((player.x - 2.0 * TILE_SIZE).abs() < 1e-4);
+}
+
+#[test]
+fn walking_stops_at_walls() {
+    let mut game = TestApp::new(1);
+    game.press(KeyCode::Left);
+    game.run_for(2.0);
+
+    // The wall is at x = 0 and the player box is 0.9 tiles wide
+    let player = game.player_translation();
+    assert!((player.x - 0.95 * TILE_SIZE).abs() < 1e-3, "{:?}", player);
+    assert_eq!(game.state(), GameState::Overworld);
+}
+
+#[test]
+fn walking_in_grass_enters_combat() {
+    let mut game = TestApp::new(1);
+    game.press(KeyCode::Right);
+    game.run_for(1.2);
+    game.release(KeyCode::Right);
+
+    // Pace back and forth inside the patch of grass
+    let mut entered_combat = false;
+    for _ in 0..60 {
+        for (hold, other) in [
+            (KeyCode::Left, KeyCode::Right),
+            (KeyCode::Right, KeyCode::Left),
+        ] {
+            game.release(other);
+            game.press(hold);
+            if game.run_until(0.5, |game| game.state() == GameState::Combat) {
+                entered_combat = true;
+            }
+        }
+        if entered_combat {
+            break;
+        }
+    }
+    assert!(entered_combat);
+}
+
+#[test]
+fn grid_mode_moves_one_tile_per_tap() {
+    let mut game = TestApp::with_config(GameConfig {
+        movement_mode: MovementMode::Grid,
+        seed: Some(1),
+        start_state: GameState::Overworld,
+        ..default()
+    });
+    game.press(KeyCode::Down);
+    game.step();
+    game.release(KeyCode::Down);
+    game.run_for(1.0);
+
+    let player = game.player_translation();
+    assert!((player.x - 2.0 * TILE_SIZE).abs() < 1e-4, "{:?}", player);
+    assert!((player.y + 3.0 * TILE_SIZE).abs() < 1e-4, "{:?}", player);
+}
+
+#[test]
+fn can_start_in_combat_against_a_named_enemy() {
+    let mut game = TestApp::with_config(GameConfig {
+        start_state: GameState::Combat,
+        enemy: Some("Skeleton".to_string()),
+        seed: Some(1),
+        ..default()
+    });
+    game.step();
+    assert_eq!(game.state(), GameState::Combat);
+
+    let names: Vec<String> = game
+        .app
+        .world
+        .query_filtered::<&Name, With<Enemy>>()
+        .iter(&game.app.world)
+        .map(|name| name.to_string())
+        .collect();
+    assert_eq!(names, vec!["Skeleton".to_string()]);
+}
+
+// Starts a battle against the named enemy and waits for the Fight/Run menu
+fn start_fight(enemy: &str) -> TestApp {
+    let mut game = TestApp::with_config(GameConfig {
+        start_state: GameState::Combat,
+        enemy: Some(enemy.to_string()),
+        seed: Some(1),
+        ..default()
+    });
+    assert!(game.run_until(5.0, |game| combat_phase(game) == CombatPhase::Fighting));
+    game
+}
+
+fn combat_phase(game: &TestApp) -> CombatPhase {
+    *game.app.world.resource::<State<CombatPhase>>().current()
+}
+
+fn player_health(game: &mut TestApp) -> isize {
+    game.app
+        .world
+        .query_filtered::<&CombatStats, With<Player>>()
+        .single(&game.app.world)
+        .health
+}
+
+// Reseeds the game so that the next escape roll comes out as wanted
+fn rig_escape(game: &mut TestApp, succeed: bool) {
+    let world = &mut game.app.world;
+    let player_speed = world
+        .query_filtered::<&CombatStats, With<Player>>()
+        .single(world)
+        .speed;
+    let enemy_speed = world
+        .query_filtered::<&CombatStats, With<Enemy>>()
+        .single(world)
+        .speed;
+    let chance = escape_chance(player_speed, enemy_speed);
+    let seed = (0..)
+        .find(|&seed| GameRng::new(seed).stream(RngStream::Combat).chance(chance) == succeed)
+        .unwrap();
+    world.insert_resource(GameRng::new(seed));
+}
+
+fn choose_run(game: &mut TestApp) {
+    for key in [KeyCode::Down, KeyCode::Return] {
+        game.press(key);
+        game.step();
+        game.release(key);
+        game.step();
+    }
+}
+
+#[test]
+fn cannot_run_from_a_boss() {
+    let mut game = start_fight("Lich");
+    choose_run(&mut game);
+    game.run_for(0.5);
+
+    // Still fighting, and the attempt didn't cost the player their turn
+    assert_eq!(game.state(), GameState::Combat);
+    assert_eq!(combat_phase(&game), CombatPhase::Fighting);
+    assert_eq!(player_health(&mut game), 10);
+}
+
+#[test]
+fn failed_run_costs_the_turn() {
+    let mut game = start_fight("Skeleton");
+    rig_escape(&mut game, false);
+    choose_run(&mut game);
+    game.run_for(0.5);
+
+    assert_eq!(combat_phase(&game), CombatPhase::Fighting);
+    assert!(player_health(&mut game) < 10);
+}
+
+#[test]
+fn successful_run_leaves_combat() {
+    let mut game = start_fight("Slime");
+    rig_escape(&mut game, true);
+    choose_run(&mut game);
+    assert_eq!(combat_phase(&game), CombatPhase::Outro);
+    assert_eq!(player_health(&mut game), 10);
+
+    assert!(game.run_until(6.0, |game| game.state() == GameState::Overworld));
+    let experience = game
+        .app
+        .world
+        .query::<&Player>()
+        .single(&game.app.world)
+        .experience;
+    assert_eq!(experience, 0);
+}
+
+#[test]
+fn menu_opens_options_over_the_overworld() {
+    let mut game = TestApp::new(1);
+    let start = game.player_translation();
+    game.press(KeyCode::Tab);
+    game.step();
+    game.release(KeyCode::Tab);
+    assert_eq!(game.state(), GameState::Options);
+
+    // Input goes to the menu, not the player
+    game.press(KeyCode::Down);
+    game.run_for(0.5);
+    assert_eq!(game.player_translation(), start);
+    assert_eq!(game.state(), GameState::Options);
+}
+
+#[test]
+fn escape_pauses_and_resumes() {
+    let mut game = TestApp::new(1);
+    let start = game.player_translation();
+    game.press(KeyCode::Escape);
+    game.step();
+    game.release(KeyCode::Escape);
+    game.step();
+    assert_eq!(game.state(), GameState::Paused);
+    assert!(game.app.world.resource::<Time>().is_paused());
+
+    game.press(KeyCode::Right);
+    game.run_for(0.5);
+    game.release(KeyCode::Right);
+    assert_eq!(game.player_translation(), start);
+
+    game.press(KeyCode::Escape);
+    game.step();
+    game.release(KeyCode::Escape);
+    game.step();
+    assert_eq!(game.state(), GameState::Overworld);
+    assert!(!game.app.world.resource::<Time>().is_paused());
+}
+
+#[test]
+fn escape_closes_options_without_pausing() {
+    let mut game = TestApp::new(1);
+    for key in [KeyCode::Tab, KeyCode::Escape] {
+        game.press(key);
+        game.step();
+        game.release(key);
+        game.step();
+    }
+    assert_eq!(game.state(), GameState::Overworld);
+
+    // Opened from the pause screen, Escape goes back to it rather than closing it too
+    for key in [KeyCode::Escape, KeyCode::Down, KeyCode::Return] {
+        game.press(key);
+        game.step();
+        game.release(key);
+        game.step();
+    }
+    assert_eq!(game.state(), GameState::Options);
+    game.press(KeyCode::Escape);
+    game.step();
+    game.release(KeyCode::Escape);
+    game.step();
+    assert_eq!(game.state(), GameState::Paused);
+}
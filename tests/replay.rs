@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use tutorial::{
+    combat::{CombatPhase, CombatStats},
+    player::Player,
+    replay::{FrameCount, Replay, ReplayRecorder},
+    test_app::TestApp,
+    GameConfig, GameState,
+};
+
+#[test]
+fn replay_reproduces_session() {
+    let path = std::env::temp_dir()
+        .join("tutorial_replay_test.ron")
+        .to_string_lossy()
+        .into_owned();
+    let _ = std::fs::remove_file(&path);
+
+    let mut recording = TestApp::with_config(GameConfig {
+        seed: Some(42),
+        record_path: Some(path.clone()),
+        start_state: GameState::Overworld,
+        ..default()
+    });
+    for (key, seconds) in [
+        (KeyCode::Right, 1.2),
+        (KeyCode::Left, 0.5),
+        (KeyCode::Up, 0.3),
+        (KeyCode::Right, 2.0),
+    ] {
+        recording.press(key);
+        recording.run_for(seconds);
+        recording.release(key);
+    }
+    recording.run_for(0.5);
+    // Written while recording, not only on exit
+    assert!(!Replay::load(&path).unwrap().frames.is_empty());
+
+    let expected_translation = recording.player_translation();
+    let expected_state = recording.state();
+    let frames = recording.app.world.resource::<FrameCount>().0;
+    recording
+        .app
+        .world
+        .resource::<ReplayRecorder>()
+        .save()
+        .unwrap();
+
+    let mut replaying = TestApp::with_config(GameConfig {
+        replay_path: Some(path),
+        start_state: GameState::Overworld,
+        ..default()
+    });
+    while replaying.app.world.resource::<FrameCount>().0 < frames {
+        replaying.step();
+    }
+
+    assert_eq!(replaying.player_translation(), expected_translation);
+    assert_eq!(replaying.state(), expected_state);
+}
+
+fn temp_path(name: &str) -> String {
+    let path = std::env::temp_dir()
+        .join(name)
+        .to_string_lossy()
+        .into_owned();
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn player_health_and_experience(game: &mut TestApp) -> (isize, usize) {
+    let (player, stats) = game
+        .app
+        .world
+        .query::<(&Player, &CombatStats)>()
+        .single(&game.app.world);
+    (stats.health, player.experience)
+}
+
+fn tap(game: &mut TestApp, key: KeyCode) {
+    game.press(key);
+    game.step();
+    game.release(key);
+    game.run_for(0.2);
+}
+
+#[test]
+fn replay_reproduces_battle_at_another_frame_rate() {
+    let path = temp_path("tutorial_replay_battle_test.ron");
+    let config = GameConfig {
+        start_state: GameState::Combat,
+        enemy: Some("Skeleton".to_string()),
+        ..default()
+    };
+
+    let mut recording = TestApp::with_config(GameConfig {
+        seed: Some(7),
+        record_path: Some(path.clone()),
+        ..config.clone()
+    });
+    assert!(recording.run_until(5.0, |game| {
+        *game.app.world.resource::<State<CombatPhase>>().current() == CombatPhase::Fighting
+    }));
+    // Trade a couple of hits, then run, so both damage and the escape roll are replayed
+    tap(&mut recording, KeyCode::Return);
+    tap(&mut recording, KeyCode::Return);
+    tap(&mut recording, KeyCode::Down);
+    for _ in 0..8 {
+        tap(&mut recording, KeyCode::Return);
+    }
+    assert!(recording.run_until(10.0, |game| game.state() == GameState::Overworld));
+    recording.run_for(0.5);
+
+    let expected = player_health_and_experience(&mut recording);
+    assert!(expected.0 < 10, "the battle should have cost some health");
+    let frames = recording.app.world.resource::<FrameCount>().0;
+    recording
+        .app
+        .world
+        .resource::<ReplayRecorder>()
+        .save()
+        .unwrap();
+
+    // Frames shorter than a fixed step, so many of them run no step at all
+    let mut replaying = TestApp::with_config(GameConfig {
+        replay_path: Some(path),
+        ..config
+    });
+    while replaying.app.world.resource::<FrameCount>().0 < frames {
+        replaying.step_by(1.0 / 144.0);
+    }
+
+    assert_eq!(replaying.state(), GameState::Overworld);
+    assert_eq!(player_health_and_experience(&mut replaying), expected);
+}
@@ -0,0 +1,73 @@
+use bevy::{
+    ecs::{event::ManualEventReader, system::CommandQueue},
+    prelude::*,
+};
+use tutorial::{
+    ascii::AsciiSheet,
+    controls::{InputContext, InputContexts},
+    test_app::TestApp,
+    transition::{
+        Transition, TransitionFinished, TransitionMidpoint, TransitionStarted, Transitions,
+    },
+    GameState,
+};
+
+// Reads the events straight from the world, since systems added after the first update
+// would not be scheduled correctly
+#[derive(Default)]
+struct Seen {
+    names: Vec<&'static str>,
+    started: ManualEventReader<TransitionStarted>,
+    midpoint: ManualEventReader<TransitionMidpoint>,
+    finished: ManualEventReader<TransitionFinished>,
+}
+
+impl Seen {
+    fn read(&mut self, world: &World) {
+        let started = self
+            .started
+            .iter(world.resource::<Events<TransitionStarted>>());
+        self.names.extend(started.map(|_| "started"));
+        let midpoint = self
+            .midpoint
+            .iter(world.resource::<Events<TransitionMidpoint>>());
+        self.names.extend(midpoint.map(|_| "midpoint"));
+        let finished = self
+            .finished
+            .iter(world.resource::<Events<TransitionFinished>>());
+        self.names.extend(finished.map(|_| "finished"));
+    }
+}
+
+fn start_transition(game: &mut TestApp, transition: Transition) {
+    let mut queue = CommandQueue::default();
+    game.app
+        .world
+        .resource_scope(|world, mut transitions: Mut<Transitions>| {
+            let mut commands = Commands::new(&mut queue, world);
+            let ascii = world.resource::<AsciiSheet>();
+            transitions.start(&mut commands, ascii, transition).unwrap();
+        });
+    queue.apply(&mut game.app.world);
+}
+
+#[test]
+fn events_arrive_in_order() {
+    let mut game = TestApp::new(1);
+    let mut seen = Seen::default();
+
+    start_transition(&mut game, Transition::fade(GameState::Combat));
+    // Locked before the transition has updated even once
+    assert!(game
+        .app
+        .world
+        .resource::<InputContexts>()
+        .is_active(InputContext::Transition));
+
+    for _ in 0..90 {
+        game.step();
+        seen.read(&game.app.world);
+    }
+    assert_eq!(seen.names, vec!["started", "midpoint", "finished"]);
+    assert_eq!(game.state(), GameState::Combat);
+}